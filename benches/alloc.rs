@@ -0,0 +1,101 @@
+//! Counts the heap allocations made while printing each file of the
+//! cargo-expand corpus.
+//!
+//!     cargo bench --bench alloc
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+struct Counter;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counter {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counter = Counter;
+
+fn main() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("cargo-expand");
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(corpus).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "rs")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let syntax_trees: Vec<syn::File> = paths
+        .iter()
+        .map(|path| syn::parse_file(&fs::read_to_string(path).unwrap()).unwrap())
+        .collect();
+
+    let mut total_allocations = 0;
+    let mut total_bytes = 0;
+    let mut total_output = 0;
+    let mut elapsed = Duration::ZERO;
+    for (path, syntax_tree) in paths.iter().zip(&syntax_trees) {
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let bytes = BYTES.load(Ordering::Relaxed);
+        let start = Instant::now();
+        let output = prettyplease::unparse(syntax_tree);
+        elapsed += start.elapsed();
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+        let bytes = BYTES.load(Ordering::Relaxed) - bytes;
+
+        println!(
+            "{:<28} {:>9} allocations {:>12} bytes {:>9} bytes of output",
+            path.file_name().unwrap().to_str().unwrap(),
+            allocations,
+            bytes,
+            output.len(),
+        );
+        total_allocations += allocations;
+        total_bytes += bytes;
+        total_output += output.len();
+    }
+    println!(
+        "{:<28} {:>9} allocations {:>12} bytes {:>9} bytes of output",
+        "total", total_allocations, total_bytes, total_output,
+    );
+    println!("elapsed: {:?}", elapsed);
+
+    // The same files again, through one Formatter whose buffers are reused.
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let outputs = prettyplease::unparse_many(&syntax_trees, &prettyplease::Config::default());
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let bytes = BYTES.load(Ordering::Relaxed) - bytes;
+    let output: usize = outputs.iter().map(String::len).sum();
+    println!(
+        "{:<28} {:>9} allocations {:>12} bytes {:>9} bytes of output",
+        "unparse_many", allocations, bytes, output,
+    );
+    println!("elapsed: {:?}", elapsed);
+}
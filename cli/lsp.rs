@@ -0,0 +1,291 @@
+//! Language server that formats documents with prettyplease, communicating
+//! over stdio.
+//!
+//! Formatting settings come from the nearest rustfmt.toml, falling back to
+//! the editor's tab size.
+
+mod rustfmt;
+
+use crate::rustfmt::Options;
+use anyhow::Result;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Formatting, RangeFormatting, Request as _};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentRangeFormattingParams,
+    FormattingOptions, OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+};
+use prettyplease::Config;
+use proc_macro2::{LineColumn, Span};
+use std::collections::HashMap;
+use syn::spanned::Spanned;
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    run(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn run(connection: &Connection) -> Result<()> {
+    // Text of the open documents
+    let mut documents = HashMap::new();
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = respond(&documents, request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some((uri, diagnostics)) = notify(&mut documents, notification)? {
+                    let params = PublishDiagnosticsParams {
+                        uri,
+                        diagnostics,
+                        version: None,
+                    };
+                    let notification =
+                        Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+                    connection
+                        .sender
+                        .send(Message::Notification(notification))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+// Keeps track of document contents. Returns the diagnostics to publish for
+// the document, if any changed.
+fn notify(
+    documents: &mut HashMap<Url, String>,
+    notification: Notification,
+) -> Result<Option<(Url, Vec<Diagnostic>)>> {
+    match notification.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params: DidOpenTextDocumentParams = serde_json::from_value(notification.params)?;
+            let document = params.text_document;
+            let diagnostics = diagnostics(&document.text);
+            documents.insert(document.uri.clone(), document.text);
+            Ok(Some((document.uri, diagnostics)))
+        }
+        DidChangeTextDocument::METHOD => {
+            let params: DidChangeTextDocumentParams = serde_json::from_value(notification.params)?;
+            let text = match params.content_changes.into_iter().last() {
+                Some(change) => change.text,
+                None => return Ok(None),
+            };
+            let uri = params.text_document.uri;
+            let diagnostics = diagnostics(&text);
+            documents.insert(uri.clone(), text);
+            Ok(Some((uri, diagnostics)))
+        }
+        DidCloseTextDocument::METHOD => {
+            let params: DidCloseTextDocumentParams = serde_json::from_value(notification.params)?;
+            let uri = params.text_document.uri;
+            documents.remove(&uri);
+            Ok(Some((uri, Vec::new())))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn respond(documents: &HashMap<Url, String>, request: Request) -> Response {
+    let result = match request.method.as_str() {
+        Formatting::METHOD => match serde_json::from_value(request.params) {
+            Ok(DocumentFormattingParams {
+                text_document,
+                options,
+                ..
+            }) => format(documents, &text_document.uri, &options, None),
+            Err(error) => return invalid_params(request.id, &error),
+        },
+        RangeFormatting::METHOD => match serde_json::from_value(request.params) {
+            Ok(DocumentRangeFormattingParams {
+                text_document,
+                range,
+                options,
+                ..
+            }) => format(documents, &text_document.uri, &options, Some(range)),
+            Err(error) => return invalid_params(request.id, &error),
+        },
+        _ => {
+            let message = format!("unsupported request {}", request.method);
+            return Response::new_err(request.id, ErrorCode::MethodNotFound as i32, message);
+        }
+    };
+    match result {
+        Ok(edits) => Response::new_ok(request.id, edits),
+        Err(error) => {
+            let message = format!("{:#}", error);
+            Response::new_err(request.id, ErrorCode::RequestFailed as i32, message)
+        }
+    }
+}
+
+fn invalid_params(id: RequestId, error: &serde_json::Error) -> Response {
+    Response::new_err(id, ErrorCode::InvalidParams as i32, error.to_string())
+}
+
+// Formats the whole document, or only the top-level items that overlap the
+// range. Returns None if the document does not parse; the parse error is
+// already reported as a diagnostic.
+fn format(
+    documents: &HashMap<Url, String>,
+    uri: &Url,
+    options: &FormattingOptions,
+    range: Option<Range>,
+) -> Result<Option<Vec<TextEdit>>> {
+    let text = match documents.get(uri) {
+        Some(text) => text,
+        None => return Ok(None),
+    };
+    parse(text, |syntax_tree| match syntax_tree {
+        Ok(syntax_tree) => format_parsed(text, syntax_tree, uri, options, range),
+        Err(_) => Ok(None),
+    })
+}
+
+fn format_parsed(
+    text: &str,
+    syntax_tree: syn::File,
+    uri: &Url,
+    options: &FormattingOptions,
+    range: Option<Range>,
+) -> Result<Option<Vec<TextEdit>>> {
+    let config = config(uri, options)?;
+
+    let range = match range {
+        Some(range) => range,
+        None => {
+            let formatted = prettyplease::unparse_with_config(&syntax_tree, &config);
+            if formatted == *text {
+                return Ok(Some(Vec::new()));
+            }
+            let range = Range::new(Position::new(0, 0), end_of_document(text));
+            return Ok(Some(vec![TextEdit::new(range, formatted)]));
+        }
+    };
+
+    let mut edits = Vec::new();
+    for item in syntax_tree.items {
+        let span = item.span();
+        let item_range = Range::new(position(text, span.start()), position(text, span.end()));
+        if item_range.end < range.start || range.end < item_range.start {
+            continue;
+        }
+        let file = syn::File {
+            shebang: None,
+            attrs: Vec::new(),
+            items: vec![item],
+        };
+        let formatted = prettyplease::unparse_with_config(&file, &config);
+        let formatted = formatted.trim_end_matches('\n');
+        if text_in(text, span) != formatted {
+            edits.push(TextEdit::new(item_range, formatted.to_owned()));
+        }
+    }
+    Ok(Some(edits))
+}
+
+fn config(uri: &Url, options: &FormattingOptions) -> Result<Config> {
+    let editor = Options {
+        indent: Some(options.tab_size as usize),
+        ..Options::default()
+    };
+    let dir = uri
+        .to_file_path()
+        .ok()
+        .and_then(|path| Some(path.parent()?.to_owned()));
+    let file_options = match dir.as_deref().and_then(rustfmt::find) {
+        Some(path) => rustfmt::load(&path)?,
+        None => Options::default(),
+    };
+    Ok(file_options.or(editor).config())
+}
+
+fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    parse(text, |syntax_tree| match syntax_tree {
+        Ok(_syntax_tree) => Vec::new(),
+        Err(error) => error
+            .into_iter()
+            .map(|error| {
+                let span = error.span();
+                Diagnostic {
+                    range: Range::new(position(text, span.start()), position(text, span.end())),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("prettyplease".to_owned()),
+                    message: error.to_string(),
+                    ..Diagnostic::default()
+                }
+            })
+            .collect(),
+    })
+}
+
+// Parses a document and hands the result to a function that must not let any
+// span escape. Every parse with span-locations records the source text in a
+// thread-local source map, which would otherwise keep growing with each edit
+// for as long as the server runs.
+fn parse<T>(text: &str, f: impl FnOnce(syn::Result<syn::File>) -> T) -> T {
+    let result = f(syn::parse_file(text));
+    proc_macro2::extra::invalidate_current_thread_spans();
+    result
+}
+
+// Converts a 1-based line and column in chars to an LSP position, which is
+// 0-based and counts UTF-16 code units.
+fn position(text: &str, line_column: LineColumn) -> Position {
+    let line = line_column.line.saturating_sub(1);
+    let character = text
+        .split('\n')
+        .nth(line)
+        .unwrap_or("")
+        .chars()
+        .take(line_column.column)
+        .map(char::len_utf16)
+        .sum::<usize>();
+    Position::new(line as u32, character as u32)
+}
+
+fn end_of_document(text: &str) -> Position {
+    let line = text.matches('\n').count();
+    let last_line = text.rsplit('\n').next().unwrap_or("");
+    let character = last_line.chars().map(char::len_utf16).sum::<usize>();
+    Position::new(line as u32, character as u32)
+}
+
+fn text_in(text: &str, span: Span) -> &str {
+    let offset = |line_column: LineColumn| {
+        let line_start: usize = text
+            .split_inclusive('\n')
+            .take(line_column.line - 1)
+            .map(str::len)
+            .sum();
+        let column: usize = text[line_start..]
+            .chars()
+            .take(line_column.column)
+            .map(char::len_utf8)
+            .sum();
+        line_start + column
+    };
+    &text[offset(span.start())..offset(span.end())]
+}
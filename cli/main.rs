@@ -0,0 +1,289 @@
+mod modules;
+mod overrides;
+mod rustfmt;
+
+use crate::rustfmt::Options;
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use prettyplease::{Config, Edition};
+use similar::TextDiff;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Format Rust source code using prettyplease.
+///
+/// Comments other than doc comments are not preserved.
+///
+/// Settings are read from the nearest rustfmt.toml, and the command line
+/// accepts a subset of rustfmt's, so that this can stand in for rustfmt in
+/// tools that invoke it through $RUSTFMT.
+#[derive(Parser, Debug)]
+#[command(name = "prettyplease", version)]
+struct Args {
+    /// Files or directories to format in place. A file is treated as a crate
+    /// root and the files of its `mod` declarations are formatted too. If no
+    /// paths are given, code is read from stdin and written to stdout.
+    paths: Vec<PathBuf>,
+
+    /// Write nothing, and instead print a diff of the changes that
+    /// formatting would make. Exits with status 1 if there are any.
+    #[arg(long)]
+    check: bool,
+
+    /// Where to write formatted files
+    #[arg(long, value_enum, default_value_t = Emit::Files)]
+    emit: Emit,
+
+    /// Maximum line width, in the same units as rustfmt's max_width
+    /// [default: 100]
+    #[arg(long, value_name = "COLUMNS")]
+    width: Option<usize>,
+
+    /// Number of spaces per level of indentation [default: 4]
+    #[arg(long, value_name = "SPACES")]
+    indent: Option<usize>,
+
+    /// Edition of Rust that the code is written in. If neither this nor
+    /// rustfmt.toml gives one, no edition is assumed and edition-dependent
+    /// syntax is printed as written
+    #[arg(long, value_parser = rustfmt::parse_edition)]
+    edition: Option<Edition>,
+
+    /// Path to a rustfmt.toml, or a directory containing one, to use instead
+    /// of searching for one next to the formatted files
+    #[arg(long, value_name = "PATH")]
+    config_path: Option<PathBuf>,
+
+    /// Settings in rustfmt.toml syntax that take precedence over the config
+    /// file: max_width, tab_spaces, edition, trailing_comma, brace_style,
+    /// control_brace_style, where_style, and the width heuristics chain_width,
+    /// fn_call_width, struct_lit_width, struct_variant_width, array_width,
+    /// attr_fn_like_width and single_line_if_else_max_width
+    #[arg(long, value_name = "KEY=VALUE,...", value_parser = overrides::parse_overrides)]
+    config: Option<Options>,
+
+    /// Accepted for compatibility with rustfmt, and ignored
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(ValueEnum, Copy, Clone, PartialEq, Debug)]
+enum Emit {
+    /// Overwrite the files in place
+    Files,
+    /// Print the formatted files to stdout
+    Stdout,
+}
+
+struct Session {
+    // Settings from the command line
+    options: Options,
+    // Settings from --config-path, in place of a rustfmt.toml search
+    config_path: Option<Options>,
+    // Settings from each rustfmt.toml found so far
+    config_files: BTreeMap<PathBuf, Options>,
+    check: bool,
+    emit: Emit,
+    // Canonical paths of files that have already been formatted
+    visited: BTreeSet<PathBuf>,
+    // Whether any file failed to format, or would change under --check
+    failed: bool,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let options = Options {
+        margin: args.width.map(rustfmt::margin),
+        indent: args.indent,
+        edition: args.edition,
+        ..Options::default()
+    };
+    let mut session = Session {
+        options: options.or(args.config.unwrap_or_default()),
+        config_path: None,
+        config_files: BTreeMap::new(),
+        check: args.check,
+        emit: args.emit,
+        visited: BTreeSet::new(),
+        failed: false,
+    };
+
+    if let Some(config_path) = &args.config_path {
+        match rustfmt::load(config_path) {
+            Ok(options) => session.config_path = Some(options),
+            Err(error) => {
+                session.error(&error);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if args.paths.is_empty() {
+        if let Err(error) = session.stdin() {
+            session.error(&error);
+        }
+    } else {
+        for path in &args.paths {
+            let result = if path.is_dir() {
+                session.dir(path)
+            } else {
+                session.crate_root(path)
+            };
+            if let Err(error) = result {
+                session.error(&error);
+            }
+        }
+    }
+
+    if session.failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+impl Session {
+    fn error(&mut self, error: &anyhow::Error) {
+        let _ = writeln!(io::stderr(), "error: {:#}", error);
+        self.failed = true;
+    }
+
+    fn stdin(&mut self) -> Result<()> {
+        let mut original = String::new();
+        io::stdin()
+            .read_to_string(&mut original)
+            .context("failed to read stdin")?;
+        let config = self.config(&env::current_dir()?)?;
+        let (_syntax_tree, formatted) = self.format(&config, &original, "<stdin>")?;
+        if self.check {
+            self.diff("<stdin>", &original, &formatted);
+        } else {
+            io::stdout().write_all(formatted.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    // Formats every .rs file under the directory, other than in hidden
+    // directories and build output.
+    fn dir(&mut self, dir: &Path) -> Result<()> {
+        let mut entries = fs::read_dir(dir)
+            .with_context(|| format!("failed to read {}", dir.display()))?
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(fs::DirEntry::file_name);
+        for entry in entries {
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            let name = entry.file_name();
+            if file_type.is_dir() {
+                let skip = name == "target" || name.to_string_lossy().starts_with('.');
+                if !skip {
+                    self.dir(&path)?;
+                }
+            } else if path.extension() == Some(OsStr::new("rs")) {
+                if let Err(error) = self.file(&path) {
+                    self.error(&error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Formats the file and, recursively, the files of its out-of-line
+    // modules.
+    fn crate_root(&mut self, path: &Path) -> Result<()> {
+        let mut pending = vec![(path.to_owned(), true)];
+        while let Some((path, mod_rs)) = pending.pop() {
+            let syntax_tree = match self.file(&path) {
+                Ok(Some(syntax_tree)) => syntax_tree,
+                Ok(None) => continue,
+                Err(error) => {
+                    self.error(&error);
+                    continue;
+                }
+            };
+            let submodules = modules::submodules(&path, mod_rs, &syntax_tree);
+            for submodule in submodules.into_iter().rev() {
+                match submodule {
+                    Ok(submodule) => pending.push((submodule.path, submodule.mod_rs)),
+                    Err(error) => self.error(&error),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the parsed file, or None if it was already formatted before.
+    fn file(&mut self, path: &Path) -> Result<Option<syn::File>> {
+        let canonical = path
+            .canonicalize()
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        if !self.visited.insert(canonical) {
+            return Ok(None);
+        }
+
+        let original = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let config = self.config(dir)?;
+        let name = path.display().to_string();
+        let (syntax_tree, formatted) = self.format(&config, &original, &name)?;
+        if self.check {
+            self.diff(&name, &original, &formatted);
+        } else if self.emit == Emit::Stdout {
+            io::stdout().write_all(formatted.as_bytes())?;
+        } else if formatted != original {
+            fs::write(path, formatted)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(Some(syntax_tree))
+    }
+
+    // Settings for formatting files in the given directory.
+    fn config(&mut self, dir: &Path) -> Result<Config> {
+        let file_options = if let Some(options) = self.config_path {
+            options
+        } else if let Some(path) = rustfmt::find(&dir.canonicalize()?) {
+            match self.config_files.get(&path) {
+                Some(options) => *options,
+                None => {
+                    let options = rustfmt::load(&path)?;
+                    self.config_files.insert(path, options);
+                    options
+                }
+            }
+        } else {
+            Options::default()
+        };
+        Ok(self.options.or(file_options).config())
+    }
+
+    fn format(&self, config: &Config, original: &str, name: &str) -> Result<(syn::File, String)> {
+        let syntax_tree = match syn::parse_file(original) {
+            Ok(syntax_tree) => syntax_tree,
+            Err(err) => {
+                let span = err.span().start();
+                bail!("{}:{}:{}: {}", name, span.line, span.column + 1, err);
+            }
+        };
+        let formatted = prettyplease::unparse_with_config(&syntax_tree, config);
+        Ok((syntax_tree, formatted))
+    }
+
+    fn diff(&mut self, name: &str, original: &str, formatted: &str) {
+        if original == formatted {
+            return;
+        }
+        let diff = TextDiff::from_lines(original, formatted);
+        let unified = diff.unified_diff().header(name, name).to_string();
+        let _ = io::stdout().write_all(unified.as_bytes());
+        self.failed = true;
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use syn::{Attribute, Expr, ExprLit, Item, Lit, Meta};
+
+pub struct Submodule {
+    pub path: PathBuf,
+    // Whether the file owns its directory, like a crate root or mod.rs, as
+    // opposed to a file like foo.rs whose submodules live in foo/.
+    pub mod_rs: bool,
+}
+
+// Locates the files of the out-of-line `mod` declarations in a source file,
+// following the same rules as rustc.
+pub fn submodules(path: &Path, mod_rs: bool, file: &syn::File) -> Vec<Result<Submodule>> {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let dir = if mod_rs {
+        parent.to_owned()
+    } else {
+        parent.join(path.file_stem().unwrap_or_default())
+    };
+    let mut submodules = Vec::new();
+    collect(&file.items, parent, &dir, true, &mut submodules);
+    submodules
+}
+
+fn collect(
+    items: &[Item],
+    parent: &Path,
+    dir: &Path,
+    top_level: bool,
+    submodules: &mut Vec<Result<Submodule>>,
+) {
+    for item in items {
+        let item = match item {
+            Item::Mod(item) => item,
+            _ => continue,
+        };
+        let path_attr = path_attr(&item.attrs);
+        let name = item.ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name);
+        if let Some((_brace, items)) = &item.content {
+            let dir = dir.join(path_attr.as_deref().unwrap_or(name));
+            collect(items, parent, &dir, false, submodules);
+            continue;
+        }
+
+        let submodule = if let Some(path_attr) = path_attr {
+            // At the top level of a file, #[path] is relative to the file's
+            // own directory even if the file is not a mod.rs.
+            let base = if top_level { parent } else { dir };
+            let path = base.join(path_attr);
+            if path.is_file() {
+                Ok(Submodule { path, mod_rs: true })
+            } else {
+                Err(anyhow!(
+                    "file not found for module `{}`: {}",
+                    name,
+                    path.display()
+                ))
+            }
+        } else {
+            let file = dir.join(format!("{}.rs", name));
+            let mod_rs = dir.join(name).join("mod.rs");
+            if file.is_file() {
+                Ok(Submodule {
+                    path: file,
+                    mod_rs: false,
+                })
+            } else if mod_rs.is_file() {
+                Ok(Submodule {
+                    path: mod_rs,
+                    mod_rs: true,
+                })
+            } else {
+                Err(anyhow!(
+                    "file not found for module `{}`: expected {} or {}",
+                    name,
+                    file.display(),
+                    mod_rs.display(),
+                ))
+            }
+        };
+        submodules.push(submodule);
+    }
+}
+
+fn path_attr(attrs: &[Attribute]) -> Option<String> {
+    for attr in attrs {
+        if let Meta::NameValue(meta) = &attr.meta {
+            if meta.path.is_ident("path") {
+                if let Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) = &meta.value
+                {
+                    return Some(lit.value());
+                }
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,39 @@
+use crate::rustfmt::{
+    self, parse_brace_style, parse_control_brace_style, parse_edition, parse_trailing_comma,
+    parse_where_style, Options,
+};
+use anyhow::{bail, Context, Result};
+
+// Parses the argument of `--config`, a comma-separated list of key=value.
+// Keys without a prettyplease equivalent are ignored.
+pub fn parse_overrides(overrides: &str) -> Result<Options> {
+    let mut options = Options::default();
+    for entry in overrides.split(',').filter(|entry| !entry.is_empty()) {
+        let (key, value) = match entry.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => bail!("invalid --config entry `{}`, expected key=value", entry),
+        };
+        match key {
+            "max_width" => options.margin = Some(rustfmt::margin(parse_number(key, value)?)),
+            "edition" => options.edition = Some(parse_edition(value)?),
+            "trailing_comma" => options.trailing_comma = Some(parse_trailing_comma(value)?),
+            "brace_style" => options.brace_style = Some(parse_brace_style(value)?),
+            "control_brace_style" => {
+                options.control_brace_style = Some(parse_control_brace_style(value)?);
+            }
+            "where_style" => options.where_style = Some(parse_where_style(value)?),
+            _ => {
+                if let Some(setting) = options.number_setting(key) {
+                    *setting = Some(parse_number(key, value)?);
+                }
+            }
+        }
+    }
+    Ok(options)
+}
+
+fn parse_number(key: &str, value: &str) -> Result<usize> {
+    value
+        .parse()
+        .with_context(|| format!("invalid value for {}: `{}`", key, value))
+}
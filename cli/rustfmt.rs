@@ -0,0 +1,219 @@
+use anyhow::{bail, Context, Result};
+use prettyplease::{BraceStyle, Config, ControlBraceStyle, Edition, TrailingComma, WhereStyle};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONFIG_FILE_NAMES: [&str; 2] = ["rustfmt.toml", ".rustfmt.toml"];
+
+// Settings given on the command line or in a rustfmt.toml, each of which may
+// be left unspecified.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Options {
+    pub margin: Option<usize>,
+    pub indent: Option<usize>,
+    pub edition: Option<Edition>,
+    pub chain_width: Option<usize>,
+    pub fn_call_width: Option<usize>,
+    pub struct_lit_width: Option<usize>,
+    pub struct_variant_width: Option<usize>,
+    pub array_width: Option<usize>,
+    pub attr_fn_like_width: Option<usize>,
+    pub single_line_if_else_max_width: Option<usize>,
+    pub trailing_comma: Option<TrailingComma>,
+    pub brace_style: Option<BraceStyle>,
+    pub control_brace_style: Option<ControlBraceStyle>,
+    pub where_style: Option<WhereStyle>,
+}
+
+impl Options {
+    // Fills in whatever is not set in self from other.
+    pub fn or(self, other: Options) -> Options {
+        Options {
+            margin: self.margin.or(other.margin),
+            indent: self.indent.or(other.indent),
+            edition: self.edition.or(other.edition),
+            chain_width: self.chain_width.or(other.chain_width),
+            fn_call_width: self.fn_call_width.or(other.fn_call_width),
+            struct_lit_width: self.struct_lit_width.or(other.struct_lit_width),
+            struct_variant_width: self.struct_variant_width.or(other.struct_variant_width),
+            array_width: self.array_width.or(other.array_width),
+            attr_fn_like_width: self.attr_fn_like_width.or(other.attr_fn_like_width),
+            single_line_if_else_max_width: self
+                .single_line_if_else_max_width
+                .or(other.single_line_if_else_max_width),
+            trailing_comma: self.trailing_comma.or(other.trailing_comma),
+            brace_style: self.brace_style.or(other.brace_style),
+            control_brace_style: self.control_brace_style.or(other.control_brace_style),
+            where_style: self.where_style.or(other.where_style),
+        }
+    }
+
+    pub fn config(self) -> Config {
+        let default = Config::default();
+        Config {
+            margin: self.margin.unwrap_or(default.margin),
+            indent: self.indent.unwrap_or(default.indent),
+            edition: self.edition.or(default.edition),
+            chain_width: self.chain_width.or(default.chain_width),
+            fn_call_width: self.fn_call_width.or(default.fn_call_width),
+            struct_lit_width: self.struct_lit_width.or(default.struct_lit_width),
+            struct_variant_width: self.struct_variant_width.or(default.struct_variant_width),
+            array_width: self.array_width.or(default.array_width),
+            attr_fn_like_width: self.attr_fn_like_width.or(default.attr_fn_like_width),
+            single_line_if_else_max_width: self
+                .single_line_if_else_max_width
+                .or(default.single_line_if_else_max_width),
+            trailing_comma: self.trailing_comma.unwrap_or(default.trailing_comma),
+            brace_style: self.brace_style.unwrap_or(default.brace_style),
+            control_brace_style: self
+                .control_brace_style
+                .unwrap_or(default.control_brace_style),
+            where_style: self.where_style.unwrap_or(default.where_style),
+            ..default
+        }
+    }
+
+    // The setting for a key that takes a number as is, other than max_width.
+    pub fn number_setting(&mut self, key: &str) -> Option<&mut Option<usize>> {
+        match key {
+            "tab_spaces" => Some(&mut self.indent),
+            "chain_width" => Some(&mut self.chain_width),
+            "fn_call_width" => Some(&mut self.fn_call_width),
+            "struct_lit_width" => Some(&mut self.struct_lit_width),
+            "struct_variant_width" => Some(&mut self.struct_variant_width),
+            "array_width" => Some(&mut self.array_width),
+            "attr_fn_like_width" => Some(&mut self.attr_fn_like_width),
+            "single_line_if_else_max_width" => Some(&mut self.single_line_if_else_max_width),
+            _ => None,
+        }
+    }
+}
+
+// The printer's margin is where it starts looking to break a line, and its
+// lines run up to about 11 columns past that. A margin of 89 produces lines
+// similar in length to rustfmt's with its default max_width of 100.
+pub fn margin(max_width: usize) -> usize {
+    max_width.saturating_sub(11)
+}
+
+pub fn parse_edition(edition: &str) -> Result<Edition> {
+    match edition {
+        "2015" => Ok(Edition::E2015),
+        "2018" => Ok(Edition::E2018),
+        "2021" => Ok(Edition::E2021),
+        "2024" => Ok(Edition::E2024),
+        _ => bail!("unsupported edition `{}`", edition),
+    }
+}
+
+pub fn parse_trailing_comma(trailing_comma: &str) -> Result<TrailingComma> {
+    match trailing_comma {
+        "Vertical" => Ok(TrailingComma::Vertical),
+        "Always" => Ok(TrailingComma::Always),
+        "Never" => Ok(TrailingComma::Never),
+        _ => bail!("unsupported trailing_comma `{}`", trailing_comma),
+    }
+}
+
+pub fn parse_brace_style(brace_style: &str) -> Result<BraceStyle> {
+    match brace_style {
+        "SameLineWhere" => Ok(BraceStyle::SameLineWhere),
+        "AlwaysNextLine" => Ok(BraceStyle::AlwaysNextLine),
+        "PreferSameLine" => Ok(BraceStyle::PreferSameLine),
+        _ => bail!("unsupported brace_style `{}`", brace_style),
+    }
+}
+
+pub fn parse_control_brace_style(control_brace_style: &str) -> Result<ControlBraceStyle> {
+    match control_brace_style {
+        "AlwaysSameLine" => Ok(ControlBraceStyle::AlwaysSameLine),
+        "ClosingNextLine" => Ok(ControlBraceStyle::ClosingNextLine),
+        "AlwaysNextLine" => Ok(ControlBraceStyle::AlwaysNextLine),
+        _ => bail!("unsupported control_brace_style `{}`", control_brace_style),
+    }
+}
+
+pub fn parse_where_style(where_style: &str) -> Result<WhereStyle> {
+    match where_style {
+        "Vertical" => Ok(WhereStyle::Vertical),
+        "Legacy" => Ok(WhereStyle::Legacy),
+        _ => bail!("unsupported where_style `{}`", where_style),
+    }
+}
+
+// Finds the rustfmt.toml that applies to files in the given directory, the
+// same way rustfmt does, by searching the directory and its ancestors.
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    for dir in dir.ancestors() {
+        for name in CONFIG_FILE_NAMES {
+            let path = dir.join(name);
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+// Loads a rustfmt.toml, or the one inside of a directory as accepted by
+// rustfmt's `--config-path`. Keys without a prettyplease equivalent are
+// ignored.
+pub fn load(path: &Path) -> Result<Options> {
+    let path = if path.is_dir() {
+        CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| path.join(name))
+            .find(|path| path.is_file())
+            .with_context(|| format!("no rustfmt.toml found in {}", path.display()))?
+    } else {
+        path.to_owned()
+    };
+
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let table: toml::Table = contents
+        .parse()
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+
+    let mut options = Options::default();
+    for (key, value) in &table {
+        let invalid = || format!("{}: invalid value for {}: `{}`", path.display(), key, value);
+        let number = || {
+            value
+                .as_integer()
+                .and_then(|number| usize::try_from(number).ok())
+                .with_context(invalid)
+        };
+        match key.as_str() {
+            "max_width" => options.margin = Some(margin(number()?)),
+            "edition" => {
+                let edition = value.as_str().with_context(invalid)?;
+                options.edition = Some(parse_edition(edition).with_context(invalid)?);
+            }
+            "trailing_comma" => {
+                let trailing_comma = value.as_str().with_context(invalid)?;
+                options.trailing_comma =
+                    Some(parse_trailing_comma(trailing_comma).with_context(invalid)?);
+            }
+            "brace_style" => {
+                let brace_style = value.as_str().with_context(invalid)?;
+                options.brace_style = Some(parse_brace_style(brace_style).with_context(invalid)?);
+            }
+            "control_brace_style" => {
+                let control_brace_style = value.as_str().with_context(invalid)?;
+                options.control_brace_style =
+                    Some(parse_control_brace_style(control_brace_style).with_context(invalid)?);
+            }
+            "where_style" => {
+                let where_style = value.as_str().with_context(invalid)?;
+                options.where_style = Some(parse_where_style(where_style).with_context(invalid)?);
+            }
+            key => {
+                if let Some(setting) = options.number_setting(key) {
+                    *setting = Some(number()?);
+                }
+            }
+        }
+    }
+    Ok(options)
+}
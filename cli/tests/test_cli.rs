@@ -0,0 +1,232 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+fn prettyplease(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_prettyplease"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn tempdir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_stdin() {
+    let output = prettyplease(&["--indent", "2"], "fn main(){let x=1;}");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "fn main() {\n  let x = 1;\n}\n",
+    );
+}
+
+#[test]
+fn test_check() {
+    let output = prettyplease(&["--check"], "fn main() {\n    let x = 1;\n}\n");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = prettyplease(&["--check"], "fn main(){}\n");
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "--- <stdin>\n+++ <stdin>\n@@ -1 +1 @@\n-fn main(){}\n+fn main() {}\n",
+    );
+}
+
+#[test]
+fn test_crate_root() {
+    let dir = tempdir("crate_root");
+    fs::create_dir_all(dir.join("a")).unwrap();
+    fs::write(dir.join("lib.rs"), "mod a; mod unrelated { }").unwrap();
+    fs::write(dir.join("a.rs"), "mod b;").unwrap();
+    fs::write(dir.join("a/b.rs"), "struct S{x:u8}").unwrap();
+    fs::write(dir.join("c.rs"), "struct C{}").unwrap();
+
+    let output = prettyplease(&[dir.join("lib.rs").to_str().unwrap()], "");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("lib.rs")).unwrap(),
+        "mod a;\nmod unrelated {}\n",
+    );
+    assert_eq!(fs::read_to_string(dir.join("a.rs")).unwrap(), "mod b;\n");
+    assert_eq!(
+        fs::read_to_string(dir.join("a/b.rs")).unwrap(),
+        "struct S {\n    x: u8,\n}\n",
+    );
+    assert_eq!(fs::read_to_string(dir.join("c.rs")).unwrap(), "struct C{}");
+
+    let output = prettyplease(&[dir.to_str().unwrap()], "");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("c.rs")).unwrap(),
+        "struct C {}\n"
+    );
+}
+
+#[test]
+fn test_missing_module() {
+    let dir = tempdir("missing_module");
+    fs::write(dir.join("main.rs"), "mod gone;").unwrap();
+    let output = prettyplease(&[dir.join("main.rs").to_str().unwrap()], "");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("error: file not found for module `gone`"));
+}
+
+#[test]
+fn test_rustfmt_compat() {
+    let dir = tempdir("rustfmt_compat");
+    fs::write(
+        dir.join("rustfmt.toml"),
+        "max_width = 50\ntab_spaces = 2\nreorder_imports = false\n",
+    )
+    .unwrap();
+    let original = "fn f() { function_with_long_name(first_argument, second_argument) }";
+    fs::write(dir.join("lib.rs"), original).unwrap();
+    let lib_rs = dir.join("lib.rs");
+    let lib_rs = lib_rs.to_str().unwrap();
+
+    let output = prettyplease(&["--emit", "stdout", "--edition", "2021", lib_rs], "");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "fn f() {\n  function_with_long_name(\n    first_argument,\n    second_argument,\n  )\n}\n",
+    );
+    assert_eq!(fs::read_to_string(lib_rs).unwrap(), original);
+
+    let output = prettyplease(&["--emit=stdout", "--config", "max_width=100", lib_rs], "");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "fn f() {\n  function_with_long_name(first_argument, second_argument)\n}\n",
+    );
+
+    // --width and max_width are the same measure.
+    let width = prettyplease(&["--emit=stdout", "--width", "65", lib_rs], "");
+    let max_width = prettyplease(&["--emit=stdout", "--config", "max_width=65", lib_rs], "");
+    assert!(width.status.success());
+    assert_eq!(width.stdout, max_width.stdout);
+    assert_eq!(
+        String::from_utf8(width.stdout).unwrap(),
+        "fn f() {\n  function_with_long_name(\n    first_argument,\n    second_argument,\n  )\n}\n",
+    );
+}
+
+#[test]
+fn test_edition() {
+    let original = "macro_rules! m {\n    ($e:expr_2021) => {};\n}\n";
+    let output = prettyplease(&[], original);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), original);
+
+    let output = prettyplease(&["--edition", "2021"], original);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "macro_rules! m {\n    ($e:expr) => {};\n}\n",
+    );
+}
+
+#[test]
+fn test_chain_width() {
+    let original = "fn f() { let x = a.b().c(); }";
+    let output = prettyplease(&["--config", "chain_width=5"], original);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "fn f() {\n    let x = a\n        .b()\n        .c();\n}\n",
+    );
+}
+
+#[test]
+fn test_width_heuristics() {
+    let dir = tempdir("width_heuristics");
+    fs::write(dir.join("rustfmt.toml"), "fn_call_width = 10\n").unwrap();
+    let original = "fn f() { g(first, second); let a = [1, 2]; }";
+    fs::write(dir.join("lib.rs"), original).unwrap();
+    let lib_rs = dir.join("lib.rs");
+    let lib_rs = lib_rs.to_str().unwrap();
+
+    let output = prettyplease(&["--emit=stdout", "--config", "array_width=3", lib_rs], "");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "fn f() {\n    g(\n        first,\n        second,\n    );\n    let a = [\n        1, 2,\n    ];\n}\n",
+    );
+}
+
+#[test]
+fn test_trailing_comma() {
+    let output = prettyplease(
+        &[
+            "--emit=stdout",
+            "--config",
+            "max_width=60,trailing_comma=Never",
+        ],
+        "fn f() { g(first_argument_is_quite_long, second_argument_is_also_long, third_argument); }",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "fn f() {\n    g(\n        first_argument_is_quite_long,\n        second_argument_is_also_long,\n        third_argument\n    );\n}\n",
+    );
+}
+
+#[test]
+fn test_brace_style() {
+    let dir = tempdir("brace_style");
+    fs::write(
+        dir.join("rustfmt.toml"),
+        "brace_style = \"AlwaysNextLine\"\n",
+    )
+    .unwrap();
+    fs::write(dir.join("lib.rs"), "fn f() { if a { b(); } }").unwrap();
+    let lib_rs = dir.join("lib.rs");
+    let lib_rs = lib_rs.to_str().unwrap();
+
+    let output = prettyplease(
+        &[
+            "--emit=stdout",
+            "--config",
+            "control_brace_style=AlwaysNextLine",
+            lib_rs,
+        ],
+        "",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "fn f()\n{\n    if a\n    {\n        b();\n    }\n}\n",
+    );
+}
+
+#[test]
+fn test_where_style() {
+    let output = prettyplease(
+        &["--emit=stdout", "--config", "where_style=Legacy"],
+        "fn f<T, U>() where T: Copy, U: Copy {}",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "fn f<T, U>()\n    where T: Copy,\n          U: Copy\n{}\n",
+    );
+}
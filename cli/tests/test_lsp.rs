@@ -0,0 +1,135 @@
+#![cfg(feature = "lsp")]
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+
+fn frame(message: &Value) -> String {
+    let body = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+// Sends the messages to a fresh server, followed by shutdown and exit, and
+// returns everything the server sent back other than its initialize result.
+fn session(messages: &[Value]) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_prettyplease-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut input = frame(&json!({
+        "jsonrpc": "2.0", "id": 0, "method": "initialize",
+        "params": {"capabilities": {}},
+    }));
+    input += &frame(&json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}));
+    for message in messages {
+        input += &frame(message);
+    }
+    input += &frame(&json!({"jsonrpc": "2.0", "id": 99, "method": "shutdown"}));
+    input += &frame(&json!({"jsonrpc": "2.0", "method": "exit"}));
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut responses = Vec::new();
+    loop {
+        let mut header = String::new();
+        if stdout.read_line(&mut header).unwrap() == 0 {
+            break;
+        }
+        let len: usize = header
+            .trim()
+            .strip_prefix("Content-Length: ")
+            .unwrap()
+            .parse()
+            .unwrap();
+        stdout.read_line(&mut String::new()).unwrap();
+        let mut body = vec![0; len];
+        stdout.read_exact(&mut body).unwrap();
+        responses.push(serde_json::from_slice::<Value>(&body).unwrap());
+    }
+    assert!(child.wait().unwrap().success());
+
+    responses.remove(0);
+    responses.pop();
+    responses
+}
+
+fn did_open(text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0", "method": "textDocument/didOpen",
+        "params": {"textDocument": {
+            "uri": "untitled:Untitled-1", "languageId": "rust", "version": 1, "text": text,
+        }},
+    })
+}
+
+#[test]
+fn test_formatting() {
+    let responses = session(&[
+        did_open("struct S{x:u8}\nfn f(){}\n"),
+        json!({
+            "jsonrpc": "2.0", "id": 1, "method": "textDocument/formatting",
+            "params": {
+                "textDocument": {"uri": "untitled:Untitled-1"},
+                "options": {"tabSize": 2, "insertSpaces": true},
+            },
+        }),
+        json!({
+            "jsonrpc": "2.0", "id": 2, "method": "textDocument/rangeFormatting",
+            "params": {
+                "textDocument": {"uri": "untitled:Untitled-1"},
+                "range": {"start": {"line": 1, "character": 0}, "end": {"line": 1, "character": 3}},
+                "options": {"tabSize": 4, "insertSpaces": true},
+            },
+        }),
+    ]);
+    assert_eq!(
+        responses,
+        [
+            json!({
+                "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics",
+                "params": {"uri": "untitled:Untitled-1", "diagnostics": []},
+            }),
+            json!({
+                "jsonrpc": "2.0", "id": 1,
+                "result": [{
+                    "range": {"start": {"line": 0, "character": 0}, "end": {"line": 2, "character": 0}},
+                    "newText": "struct S {\n  x: u8,\n}\nfn f() {}\n",
+                }],
+            }),
+            json!({
+                "jsonrpc": "2.0", "id": 2,
+                "result": [{
+                    "range": {"start": {"line": 1, "character": 0}, "end": {"line": 1, "character": 8}},
+                    "newText": "fn f() {}",
+                }],
+            }),
+        ],
+    );
+}
+
+#[test]
+fn test_diagnostics() {
+    let responses = session(&[did_open("fn f() {\n    let x = ;\n}\n")]);
+    assert_eq!(
+        responses,
+        [json!({
+            "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics",
+            "params": {
+                "uri": "untitled:Untitled-1",
+                "diagnostics": [{
+                    "range": {"start": {"line": 1, "character": 12}, "end": {"line": 1, "character": 13}},
+                    "severity": 1,
+                    "source": "prettyplease",
+                    "message": "expected an expression",
+                }],
+            },
+        })],
+    );
+}
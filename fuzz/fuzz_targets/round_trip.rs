@@ -11,5 +11,7 @@ fuzz_target!(|data: &[u8]| {
     let Ok(syntax_tree) = syn::parse_file(string) else {
         return;
     };
-    let _ = prettyplease::unparse(&syntax_tree);
+    if let Err(err) = prettyplease::unparse_checked(&syntax_tree) {
+        panic!("{}", err);
+    }
 });
@@ -1,12 +1,21 @@
 // Adapted from https://github.com/rust-lang/rust/blob/1.57.0/compiler/rustc_ast_pretty/src/pp.rs.
 // See "Algorithm notes" in the crate-level rustdoc.
 
+use crate::config::Config;
+use crate::hygiene::Renames;
 use crate::ring::RingBuffer;
-use crate::{MARGIN, MIN_SPACE};
-use std::borrow::Cow;
-use std::cmp;
-use std::collections::VecDeque;
-use std::iter;
+use crate::shorten::Shortening;
+use crate::{INDENT, MIN_SPACE};
+use alloc::borrow::{Cow, ToOwned};
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp;
+use core::fmt::{self, Display, Write as _};
+use core::iter;
+use core::mem;
+use core::ops::Deref;
+use core::str;
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum Breaks {
@@ -33,12 +42,144 @@ pub struct BeginToken {
 
 #[derive(Clone)]
 pub enum Token {
-    String(Cow<'static, str>),
+    String(Text),
     Break(BreakToken),
     Begin(BeginToken),
     End,
 }
 
+/// Text of a String token.
+///
+/// Keywords and punctuation are borrowed, and most identifiers and literals
+/// are short enough to be stored inline, so that printing them does not
+/// involve a heap allocation each. This takes the place of the
+/// `Cow<'static, str>` that String tokens used to hold, which converts into
+/// `Text` with `From`.
+#[derive(Clone)]
+pub enum Text {
+    Static(&'static str),
+    Inline(InlineText),
+    Owned(String),
+}
+
+// A string of up to CAPACITY bytes. The bytes up to len are always valid
+// UTF-8, since they are only ever appended to from a whole &str.
+#[derive(Copy, Clone)]
+pub struct InlineText {
+    len: u8,
+    bytes: [u8; InlineText::CAPACITY],
+}
+
+impl InlineText {
+    // Fills out the space taken by Text's largest variant.
+    const CAPACITY: usize = 30;
+
+    const EMPTY: Self = InlineText {
+        len: 0,
+        bytes: [0; InlineText::CAPACITY],
+    };
+
+    // Appends the string if it fits, and returns whether it did.
+    fn push_str(&mut self, string: &str) -> bool {
+        let len = self.len as usize;
+        if len + string.len() > InlineText::CAPACITY {
+            return false;
+        }
+        self.bytes[len..len + string.len()].copy_from_slice(string.as_bytes());
+        self.len += string.len() as u8;
+        true
+    }
+
+    fn as_str(&self) -> &str {
+        let bytes = &self.bytes[..self.len as usize];
+        // SAFETY: the bytes are a concatenation of whole strs, see push_str.
+        unsafe { str::from_utf8_unchecked(bytes) }
+    }
+}
+
+impl Text {
+    pub fn new() -> Self {
+        Text::Inline(InlineText::EMPTY)
+    }
+
+    // Formats a value, such as an identifier or literal, without allocating
+    // if its text fits inline.
+    pub fn display(value: &dyn Display) -> Self {
+        let mut text = Text::new();
+        write!(text, "{}", value).unwrap();
+        text
+    }
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Text::new()
+    }
+}
+
+impl Deref for Text {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Text::Static(string) => string,
+            Text::Inline(inline) => inline.as_str(),
+            Text::Owned(string) => string,
+        }
+    }
+}
+
+impl fmt::Write for Text {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        match self {
+            Text::Inline(inline) => {
+                if !inline.push_str(string) {
+                    let mut owned = String::with_capacity(inline.len as usize + string.len());
+                    owned.push_str(inline.as_str());
+                    owned.push_str(string);
+                    *self = Text::Owned(owned);
+                }
+            }
+            Text::Static(existing) => {
+                let mut owned = existing.to_owned();
+                owned.push_str(string);
+                *self = Text::Owned(owned);
+            }
+            Text::Owned(owned) => owned.push_str(string),
+        }
+        Ok(())
+    }
+}
+
+impl From<&'static str> for Text {
+    fn from(string: &'static str) -> Self {
+        Text::Static(string)
+    }
+}
+
+impl From<String> for Text {
+    fn from(string: String) -> Self {
+        Text::Owned(string)
+    }
+}
+
+impl From<Cow<'static, str>> for Text {
+    fn from(string: Cow<'static, str>) -> Self {
+        match string {
+            Cow::Borrowed(string) => Text::Static(string),
+            Cow::Owned(string) => Text::Owned(string),
+        }
+    }
+}
+
+impl From<char> for Text {
+    fn from(ch: char) -> Self {
+        let mut text = Text::new();
+        text.write_char(ch).unwrap();
+        text
+    }
+}
+
 #[derive(Copy, Clone)]
 enum PrintFrame {
     Fits(Breaks),
@@ -69,19 +210,68 @@ pub struct Printer {
     indent: usize,
     // Buffered indentation to avoid writing trailing whitespace
     pending_indentation: usize,
+    // Descriptions of syntax that could not be printed, collected instead of
+    // panicking when the printer is only probing whether a node is printable
+    unsupported: Option<Vec<String>>,
+    pub(crate) config: Config,
+    // Local bindings to print under a different name to keep identifiers from
+    // different hygiene contexts apart
+    pub(crate) renames: Renames,
+    // Absolute paths to print in shortened form within the current module
+    pub(crate) shortening: Shortening,
+    // Number of expressions being printed that contain the current one
+    pub(crate) depth: usize,
+    // Whether an expression was left out for exceeding the depth limit
+    pub(crate) too_deep: bool,
+    // Ring-buffer indices of the outermost and innermost of the Begins that
+    // are to be measured up to the next Break, as requested by
+    // measure_to_next_break
+    measure: Option<(usize, usize)>,
+    // Operations to lay out later on another thread, in place of printing
+    #[cfg(feature = "rayon")]
+    pub(crate) recording: Option<Vec<Recorded>>,
+}
+
+// The sequence of calls that a printer received, which produce the same
+// output when replayed into a fresh printer.
+#[cfg(feature = "rayon")]
+pub(crate) enum Recorded {
+    Begin(BeginToken),
+    End,
+    Break(BreakToken),
+    String(Text),
+    Offset(isize),
+    EndWithMaxWidth(isize),
+    MeasureToNextBreak(usize),
+    // The answer that was given to a call to ends_with, which the replay
+    // needs to confirm
+    EndsWith(char, bool),
 }
 
 #[derive(Clone)]
 struct BufEntry {
     token: Token,
     size: isize,
+    // For a Begin, width of the box's contents up to the point where it is
+    // decided whether the box fits, if that is not its end
+    measured: Option<isize>,
+}
+
+impl Default for Printer {
+    fn default() -> Self {
+        Printer::new()
+    }
 }
 
 impl Printer {
     pub fn new() -> Self {
+        Printer::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Self {
         Printer {
             out: String::new(),
-            space: MARGIN,
+            space: config.margin as isize,
             buf: RingBuffer::new(),
             left_total: 0,
             right_total: 0,
@@ -89,31 +279,103 @@ impl Printer {
             print_stack: Vec::new(),
             indent: 0,
             pending_indentation: 0,
+            unsupported: None,
+            config,
+            renames: Renames::new(),
+            shortening: Shortening::default(),
+            depth: 0,
+            too_deep: false,
+            measure: None,
+            #[cfg(feature = "rayon")]
+            recording: None,
+        }
+    }
+
+    pub(crate) fn probe() -> Self {
+        Printer {
+            unsupported: Some(Vec::new()),
+            ..Printer::new()
+        }
+    }
+
+    pub(crate) fn into_unsupported(self) -> Vec<String> {
+        self.unsupported.unwrap_or_default()
+    }
+
+    pub(crate) fn unsupported(&mut self, message: String) {
+        match &mut self.unsupported {
+            Some(unsupported) => unsupported.push(message),
+            None => unimplemented!("{}", message),
         }
     }
 
     pub fn eof(mut self) -> String {
+        self.flush();
+        self.out
+    }
+
+    // Moves the output into `out`, like `eof`, but keeps the printer and its
+    // buffers around for printing something else. The string previously in
+    // `out` is cleared and becomes the printer's output buffer, so that its
+    // memory is reused.
+    pub fn finish(&mut self, out: &mut String) {
+        self.flush();
+        mem::swap(&mut self.out, out);
+        self.reset();
+    }
+
+    // Discards everything printed so far, keeping the configuration and the
+    // memory allocated for buffers.
+    pub fn reset(&mut self) {
+        self.out.clear();
+        self.space = self.config.margin as isize;
+        self.buf.clear();
+        self.left_total = 0;
+        self.right_total = 0;
+        self.scan_stack.clear();
+        self.print_stack.clear();
+        self.indent = 0;
+        self.pending_indentation = 0;
+        self.renames.clear();
+        self.shortening = Shortening::default();
+        self.depth = 0;
+        self.too_deep = false;
+        self.measure = None;
+    }
+
+    fn flush(&mut self) {
         if !self.scan_stack.is_empty() {
             self.check_stack(0);
             self.advance_left();
         }
-        self.out
     }
 
     pub fn scan_begin(&mut self, token: BeginToken) {
+        #[cfg(feature = "rayon")]
+        if let Some(recording) = &mut self.recording {
+            recording.push(Recorded::Begin(token));
+            return;
+        }
         if self.scan_stack.is_empty() {
             self.left_total = 1;
             self.right_total = 1;
             self.buf.clear();
+            self.measure = None;
         }
         let right = self.buf.push(BufEntry {
             token: Token::Begin(token),
             size: -self.right_total,
+            measured: None,
         });
         self.scan_stack.push_back(right);
     }
 
     pub fn scan_end(&mut self) {
+        #[cfg(feature = "rayon")]
+        if let Some(recording) = &mut self.recording {
+            recording.push(Recorded::End);
+            return;
+        }
         if self.scan_stack.is_empty() {
             self.print_end();
         } else {
@@ -139,28 +401,44 @@ impl Printer {
             let right = self.buf.push(BufEntry {
                 token: Token::End,
                 size: -1,
+                measured: None,
             });
             self.scan_stack.push_back(right);
         }
     }
 
     pub fn scan_break(&mut self, token: BreakToken) {
+        #[cfg(feature = "rayon")]
+        if let Some(recording) = &mut self.recording {
+            recording.push(Recorded::Break(token));
+            return;
+        }
+        let measure = self.measure.take();
         if self.scan_stack.is_empty() {
             self.left_total = 1;
             self.right_total = 1;
             self.buf.clear();
         } else {
             self.check_stack(0);
+            if let Some((outermost, innermost)) = measure {
+                self.settle_measure(outermost, innermost);
+            }
         }
         let right = self.buf.push(BufEntry {
             token: Token::Break(token),
             size: -self.right_total,
+            measured: None,
         });
         self.scan_stack.push_back(right);
         self.right_total += token.blank_space as isize;
     }
 
-    pub fn scan_string(&mut self, string: Cow<'static, str>) {
+    pub fn scan_string(&mut self, string: Text) {
+        #[cfg(feature = "rayon")]
+        if let Some(recording) = &mut self.recording {
+            recording.push(Recorded::String(string));
+            return;
+        }
         if self.scan_stack.is_empty() {
             self.print_string(string);
         } else {
@@ -168,6 +446,7 @@ impl Printer {
             self.buf.push(BufEntry {
                 token: Token::String(string),
                 size: len,
+                measured: None,
             });
             self.right_total += len;
             self.check_stream();
@@ -175,6 +454,11 @@ impl Printer {
     }
 
     pub fn offset(&mut self, offset: isize) {
+        #[cfg(feature = "rayon")]
+        if let Some(recording) = &mut self.recording {
+            recording.push(Recorded::Offset(offset));
+            return;
+        }
         match &mut self.buf.last_mut().token {
             Token::Break(token) => token.offset += offset,
             Token::Begin(_) => {}
@@ -183,24 +467,87 @@ impl Printer {
     }
 
     pub fn end_with_max_width(&mut self, max: isize) {
+        #[cfg(feature = "rayon")]
+        if let Some(recording) = &mut self.recording {
+            recording.push(Recorded::EndWithMaxWidth(max));
+            return;
+        }
+        let mut too_wide = false;
+        if let Some(index) = self.open_begin(1) {
+            let entry = &mut self.buf[index];
+            if let Token::Begin(_) = entry.token {
+                if entry.size < 0 {
+                    let actual_width = entry.measured.unwrap_or(entry.size + self.right_total);
+                    too_wide = actual_width > max;
+                }
+                if too_wide {
+                    entry.measured = None;
+                }
+            }
+        }
+        if too_wide {
+            // Settle the size of breaks that are still pending, such as the
+            // last one inside of a nested box, so that only this box is forced
+            // to break.
+            self.check_stack(0);
+            self.buf.push(BufEntry {
+                token: Token::String(Text::Static("")),
+                size: SIZE_INFINITY,
+                measured: None,
+            });
+            self.right_total += SIZE_INFINITY;
+        }
+        self.scan_end();
+    }
+
+    // Decides whether the given number of innermost open boxes fit based on
+    // their contents up to the next break, rather than all of their contents.
+    // Used where the rest of the box, like the body of a closure that is the
+    // last argument of a call, is able to break on its own.
+    pub fn measure_to_next_break(&mut self, boxes: usize) {
+        #[cfg(feature = "rayon")]
+        if let Some(recording) = &mut self.recording {
+            recording.push(Recorded::MeasureToNextBreak(boxes));
+            return;
+        }
+        self.measure = self.open_begin(boxes).zip(self.open_begin(1));
+    }
+
+    fn settle_measure(&mut self, outermost: usize, innermost: usize) {
+        // Nothing to do if the box ended before reaching a break.
+        if !self.buf.index_range().contains(&outermost) || self.buf[outermost].size >= 0 {
+            return;
+        }
+        for &index in self.scan_stack.iter().rev() {
+            if index > innermost {
+                continue;
+            } else if index < outermost {
+                break;
+            }
+            let entry = &mut self.buf[index];
+            if let Token::Begin(_) = entry.token {
+                // A negative size means the box has not ended yet.
+                if entry.size < 0 {
+                    entry.measured = Some(entry.size + self.right_total);
+                }
+            }
+        }
+    }
+
+    // Ring-buffer index of the Begin of the given number of innermost boxes
+    // that have not ended, counting outward.
+    fn open_begin(&self, mut boxes: usize) -> Option<usize> {
         let mut depth = 1;
         for &index in self.scan_stack.iter().rev() {
-            let entry = &self.buf[index];
-            match entry.token {
+            match self.buf[index].token {
                 Token::Begin(_) => {
                     depth -= 1;
                     if depth == 0 {
-                        if entry.size < 0 {
-                            let actual_width = entry.size + self.right_total;
-                            if actual_width > max {
-                                self.buf.push(BufEntry {
-                                    token: Token::String(Cow::Borrowed("")),
-                                    size: SIZE_INFINITY,
-                                });
-                                self.right_total += SIZE_INFINITY;
-                            }
+                        boxes -= 1;
+                        if boxes == 0 {
+                            return Some(index);
                         }
-                        break;
+                        depth = 1;
                     }
                 }
                 Token::End => depth += 1,
@@ -208,10 +555,20 @@ impl Printer {
                 Token::String(_) => unreachable!(),
             }
         }
-        self.scan_end();
+        None
     }
 
     pub fn ends_with(&self, ch: char) -> bool {
+        #[cfg(feature = "rayon")]
+        if let Some(recording) = &self.recording {
+            // Assume nothing has been flushed from the buffer yet, which the
+            // replay checks.
+            let ends_with = recording.iter().rev().find_map(|recorded| match recorded {
+                Recorded::String(string) => Some(string.ends_with(ch)),
+                _ => None,
+            });
+            return ends_with.unwrap_or(false);
+        }
         for i in self.buf.index_range().rev() {
             if let Token::String(token) = &self.buf[i].token {
                 return token.ends_with(ch);
@@ -220,6 +577,17 @@ impl Printer {
         self.out.ends_with(ch)
     }
 
+    // Like ends_with, for use in deciding what to print next. While recording,
+    // the answer is recorded too, for the replay to confirm.
+    pub(crate) fn ends_with_recorded(&mut self, ch: char) -> bool {
+        let ends_with = self.ends_with(ch);
+        #[cfg(feature = "rayon")]
+        if let Some(recording) = &mut self.recording {
+            recording.push(Recorded::EndsWith(ch, ends_with));
+        }
+        ends_with
+    }
+
     fn check_stream(&mut self) {
         while self.right_total - self.left_total > self.space {
             if *self.scan_stack.front().unwrap() == self.buf.index_range().start {
@@ -248,7 +616,9 @@ impl Printer {
                     self.left_total += token.blank_space as isize;
                     self.print_break(token, left.size);
                 }
-                Token::Begin(token) => self.print_begin(token, left.size),
+                Token::Begin(token) => {
+                    self.print_begin(token, left.measured.unwrap_or(left.size));
+                }
                 Token::End => self.print_end(),
             }
 
@@ -311,7 +681,8 @@ impl Printer {
         if size > self.space {
             self.print_stack
                 .push(PrintFrame::Broken(self.indent, token.breaks));
-            self.indent = usize::try_from(self.indent as isize + token.offset).unwrap();
+            let offset = self.scale_offset(token.offset);
+            self.indent = usize::try_from(self.indent as isize + offset).unwrap();
         } else {
             self.print_stack.push(PrintFrame::Fits(token.breaks));
         }
@@ -359,9 +730,10 @@ impl Printer {
                 self.out.push('·');
             }
             self.out.push('\n');
-            let indent = self.indent as isize + token.offset;
+            let indent = self.indent as isize + self.scale_offset(token.offset);
             self.pending_indentation = usize::try_from(indent).unwrap();
-            self.space = cmp::max(MARGIN - indent, MIN_SPACE);
+            let margin = self.config.margin as isize;
+            self.space = cmp::max(margin - indent, cmp::min(margin, MIN_SPACE));
             if !token.post_break.is_empty() {
                 self.print_indent();
                 self.out.push_str(token.post_break);
@@ -370,12 +742,18 @@ impl Printer {
         }
     }
 
-    fn print_string(&mut self, string: Cow<'static, str>) {
+    fn print_string(&mut self, string: Text) {
         self.print_indent();
         self.out.push_str(&string);
         self.space -= string.len() as isize;
     }
 
+    // Offsets are expressed in multiples of INDENT, which stand for the
+    // configured indentation width.
+    fn scale_offset(&self, offset: isize) -> isize {
+        offset * self.config.indent as isize / INDENT
+    }
+
     fn print_indent(&mut self) {
         self.out.reserve(self.pending_indentation);
         self.out
@@ -1,9 +1,13 @@
 use crate::algorithm::Printer;
+use crate::config::Edition;
 use crate::fixup::FixupContext;
+use crate::iter::IterDelimited;
 use crate::path::PathKind;
 use crate::INDENT;
+use alloc::string::String;
+use alloc::vec::Vec;
 use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
-use syn::{AttrStyle, Attribute, Expr, Lit, MacroDelimiter, Meta, MetaList, MetaNameValue};
+use syn::{AttrStyle, Attribute, Expr, Lit, MacroDelimiter, Meta, MetaList, MetaNameValue, Path};
 
 impl Printer {
     pub fn outer_attrs(&mut self, attrs: &[Attribute]) {
@@ -76,21 +80,29 @@ impl Printer {
             AttrStyle::Inner(_) => "#!",
         });
         self.word("[");
+        let requires_unsafe =
+            self.config.edition >= Some(Edition::E2024) && requires_unsafe(&attr.meta);
+        if requires_unsafe {
+            self.word("unsafe(");
+        }
         self.meta(&attr.meta);
+        if requires_unsafe {
+            self.word(")");
+        }
         self.word("]");
         self.space();
     }
 
     fn meta(&mut self, meta: &Meta) {
         match meta {
-            Meta::Path(path) => self.path(path, PathKind::Simple),
+            Meta::Path(path) => self.meta_path(path),
             Meta::List(meta) => self.meta_list(meta),
             Meta::NameValue(meta) => self.meta_name_value(meta),
         }
     }
 
     fn meta_list(&mut self, meta: &MetaList) {
-        self.path(&meta.path, PathKind::Simple);
+        self.meta_path(&meta.path);
         let delimiter = match meta.delimiter {
             MacroDelimiter::Paren(_) => Delimiter::Parenthesis,
             MacroDelimiter::Brace(_) => Delimiter::Brace,
@@ -101,11 +113,25 @@ impl Printer {
     }
 
     fn meta_name_value(&mut self, meta: &MetaNameValue) {
-        self.path(&meta.path, PathKind::Simple);
+        self.meta_path(&meta.path);
         self.word(" = ");
         self.expr(&meta.value, FixupContext::NONE);
     }
 
+    // Attribute paths are parsed by syn with keywords allowed, as in
+    // `#[unsafe(no_mangle)]`, so their segments are printed without raw
+    // escaping.
+    fn meta_path(&mut self, path: &Path) {
+        let skip = self.shortening.skip(path);
+        for segment in path.segments.iter().skip(skip).delimited() {
+            if !segment.is_first || skip == 0 && path.leading_colon.is_some() {
+                self.word("::");
+            }
+            self.token_ident(&segment.ident);
+            self.path_arguments(&segment.arguments, PathKind::Simple);
+        }
+    }
+
     fn attr_tokens(&mut self, tokens: TokenStream) {
         let mut stack = Vec::new();
         stack.push((tokens.into_iter().peekable(), Delimiter::None));
@@ -127,7 +153,7 @@ impl Printer {
                     if let Word = state {
                         space(self);
                     }
-                    self.ident(&ident);
+                    self.token_ident(&ident);
                     state = Word;
                 }
                 Some(TokenTree::Punct(punct)) => {
@@ -185,7 +211,7 @@ impl Printer {
                                 self.zerobreak();
                             }
                             self.offset(-INDENT);
-                            self.end();
+                            self.end_with_width(self.config.attr_fn_like_width);
                             self.word(")");
                             state = Punct;
                         }
@@ -209,6 +235,12 @@ impl Printer {
     }
 }
 
+// Attributes that must be written as #[unsafe(...)] starting in Rust 2024.
+fn requires_unsafe(meta: &Meta) -> bool {
+    let path = meta.path();
+    path.is_ident("export_name") || path.is_ident("link_section") || path.is_ident("no_mangle")
+}
+
 fn value_of_attribute(requested: &str, attr: &Attribute) -> Option<String> {
     let value = match &attr.meta {
         Meta::NameValue(meta) if meta.path.is_ident(requested) => &meta.value,
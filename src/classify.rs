@@ -1,5 +1,5 @@
+use core::ops::ControlFlow;
 use proc_macro2::{Delimiter, TokenStream, TokenTree};
-use std::ops::ControlFlow;
 use syn::punctuated::Punctuated;
 use syn::{Expr, MacroDelimiter, Path, PathArguments, ReturnType, Token, Type, TypeParamBound};
 
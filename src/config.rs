@@ -0,0 +1,223 @@
+/// Options that control the printed output.
+///
+/// The default configuration produces the same output as
+/// [`unparse`][crate::unparse].
+///
+/// ```
+/// let config = prettyplease::Config {
+///     edition: Some(prettyplease::Edition::E2024),
+///     ..prettyplease::Config::default()
+/// };
+/// # let _ = config;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Target line width. Lines are broken to fit within this many columns
+    /// where possible, but a deeply indented line is still given up to 60
+    /// columns of space after its indentation, so it may run past the margin.
+    pub margin: usize,
+    /// Number of spaces per level of indentation.
+    pub indent: usize,
+    /// Edition of the Rust language that the printed code targets.
+    ///
+    /// With no edition, the output does not depend on one: no identifier is
+    /// escaped for being an edition keyword, and syntax is printed as it
+    /// appears in the syntax tree apart from `dyn`, which is always written.
+    pub edition: Option<Edition>,
+    /// Rename local variables that are spelled the same but come from
+    /// different hygiene contexts, such as `x` and `x_1`, so that printed
+    /// macro-expanded code keeps its meaning.
+    ///
+    /// Only spans created by the compiler carry hygiene information, so this
+    /// has an effect only when printing from inside of a procedural macro.
+    ///
+    /// This is best-effort. The compiler does not expose a span's hygiene
+    /// context, so it is read from the span's `Debug` representation, which
+    /// is not guaranteed to stay the same. Bindings are renamed throughout
+    /// the top-level item that contains them, which may rename some that do
+    /// not clash because they are in separate scopes.
+    #[cfg(feature = "hygiene")]
+    pub hygiene: bool,
+    /// Print absolute paths to items of the standard prelude, such as
+    /// `::core::option::Option::Some`, as their bare name.
+    ///
+    /// A path is left alone if its name is declared or glob-imported in the
+    /// same module, or if the module is `#[no_implicit_prelude]`.
+    pub shorten_prelude_paths: bool,
+    /// Collect absolute paths that occur repeatedly within a module into
+    /// `use` items at the top of that module, and refer to them by name.
+    ///
+    /// A path is imported only if its name is not otherwise used anywhere in
+    /// the module, including inside of macro invocations.
+    pub import_repeated_paths: bool,
+    /// Maximum depth of nested expressions, beyond which printing fails with
+    /// a [`DepthError`][crate::DepthError] rather than risk overflowing the
+    /// stack. A chain of binary operators like `a + b + c`, or of method
+    /// calls, counts as a single level no matter how long it is.
+    ///
+    /// The default of 512 leaves room to spare in a 2 MiB thread stack, the
+    /// size Rust gives spawned threads, even in a debug build where the most
+    /// stack-hungry kinds of expression take about 2 KiB per level. `None`
+    /// removes the limit.
+    pub max_depth: Option<usize>,
+    /// Print arrays whose elements are all integer literals, such as lookup
+    /// tables, in hexadecimal padded to a common width, like `[0x00, 0x0a,
+    /// 0xff, ...]`, so that the elements line up in columns.
+    ///
+    /// Only arrays of at least 8 elements that all have the same suffix, or
+    /// none, are printed this way.
+    pub hex_int_tables: bool,
+    /// Put every link of a chain of method calls, field accesses and
+    /// `.await` on its own line if the chain is wider than this many columns,
+    /// like rustfmt's `chain_width`. By default a chain is broken only if it
+    /// does not fit within the margin.
+    pub chain_width: Option<usize>,
+    /// Put every link of a chain on its own line if the chain has more than
+    /// this many links, regardless of its width.
+    pub chain_links: Option<usize>,
+    /// Keep the arguments of a call on the same line as the call if the last
+    /// one is a closure, block, struct literal, array or `match` whose body
+    /// can span multiple lines, like rustfmt does, instead of putting every
+    /// argument on its own line.
+    pub overflow_last_arg: bool,
+    /// Maximum width of the arguments of a function call, method call or
+    /// function-like macro that are kept on one line, like rustfmt's
+    /// `fn_call_width`.
+    pub fn_call_width: Option<usize>,
+    /// Maximum width of a struct literal, including its path, that is kept on
+    /// one line.
+    pub struct_lit_width: Option<usize>,
+    /// Maximum width of the fields of an enum variant with named fields that
+    /// are kept on one line, like rustfmt's `struct_variant_width`.
+    pub struct_variant_width: Option<usize>,
+    /// Maximum width of the elements of an array literal that are kept on one
+    /// line, like rustfmt's `array_width`.
+    pub array_width: Option<usize>,
+    /// Maximum width of the arguments of an attribute like `#[derive(...)]`
+    /// that are kept on one line, like rustfmt's `attr_fn_like_width`.
+    pub attr_fn_like_width: Option<usize>,
+    /// Maximum width of an `if`-`else` expression that is kept on one line,
+    /// like rustfmt's `single_line_if_else_max_width`.
+    pub single_line_if_else_max_width: Option<usize>,
+    /// When to put a comma after the last element of a list, such as the
+    /// arguments of a call, the fields of a struct, or the arms of a `match`.
+    pub trailing_comma: TrailingComma,
+    /// Where to put the opening brace of the body of an item, such as a
+    /// function, impl, trait, struct, enum or module.
+    pub brace_style: BraceStyle,
+    /// Where to put the braces of the body of `if`, `else`, `match`, and
+    /// loops.
+    pub control_brace_style: ControlBraceStyle,
+    /// Layout of the where-clause of an item.
+    pub where_style: WhereStyle,
+    /// Keep a where-clause that fits on the line of the item's signature,
+    /// like `fn f<T>(t: T) where T: Copy {`, rather than always putting it on
+    /// lines of its own.
+    pub where_same_line: bool,
+    /// Break a list of bounds that does not fit on one line, like
+    /// `T: A + B + C` or `dyn A + B + C`, with one bound per line, rather than
+    /// fitting as many bounds on each line as possible.
+    pub vertical_bounds: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            margin: 89,
+            indent: 4,
+            edition: None,
+            #[cfg(feature = "hygiene")]
+            hygiene: false,
+            shorten_prelude_paths: false,
+            import_repeated_paths: false,
+            max_depth: Some(512),
+            hex_int_tables: false,
+            chain_width: None,
+            chain_links: None,
+            overflow_last_arg: false,
+            fn_call_width: None,
+            struct_lit_width: Some(34),
+            struct_variant_width: None,
+            array_width: None,
+            attr_fn_like_width: None,
+            single_line_if_else_max_width: None,
+            trailing_comma: TrailingComma::Vertical,
+            brace_style: BraceStyle::SameLineWhere,
+            control_brace_style: ControlBraceStyle::AlwaysSameLine,
+            where_style: WhereStyle::Vertical,
+            where_same_line: false,
+            vertical_bounds: false,
+        }
+    }
+}
+
+/// Edition of the Rust language that the printed code targets. Each edition
+/// prints as the one before it, except as described for that edition.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Edition {
+    /// Rust 2015. A trait object type written without `dyn`, like
+    /// `Box<Trait>`, is printed without it, whereas other editions add it. In
+    /// `macro_rules!`, the fragment specifiers `pat_param` and `expr_2021` are
+    /// printed as `pat` and `expr`, which match the same syntax here.
+    E2015,
+    /// Rust 2018, in which `async`, `await`, `dyn` and `try` are keywords, so
+    /// identifiers by those names are printed raw, like `r#async`.
+    E2018,
+    /// Rust 2021, in which `pat` matches or-patterns, so the `pat_param`
+    /// fragment specifier is printed as written. `TryFrom`, `TryInto` and
+    /// `FromIterator` count as part of the prelude for
+    /// [`shorten_prelude_paths`][Config::shorten_prelude_paths].
+    E2021,
+    /// Rust 2024, in which `gen` is a keyword and printed raw as `r#gen`, and
+    /// `expr` matches `const` and `_` expressions, so the `expr_2021` fragment
+    /// specifier is printed as written. Every `extern` block is printed as
+    /// `unsafe extern`, and the attributes `export_name`, `link_section` and
+    /// `no_mangle` as `#[unsafe(...)]`.
+    E2024,
+}
+
+/// When to put a comma after the last element of a list.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TrailingComma {
+    /// Only if the list is broken over multiple lines.
+    Vertical,
+    /// Always, even if the list fits on one line, like `f(a, b,)`.
+    Always,
+    /// Never, except where it is required, like in a tuple of one element.
+    Never,
+}
+
+/// Where to put the opening brace of the body of an item.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BraceStyle {
+    /// On the same line as the item, unless the item has a where-clause, in
+    /// which case on a line of its own after the where-clause.
+    SameLineWhere,
+    /// Always on a line of its own, also known as Allman style.
+    AlwaysNextLine,
+    /// On the same line as the item, or as the last line of its
+    /// where-clause.
+    PreferSameLine,
+}
+
+/// Where to put the braces of the body of a control flow expression.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ControlBraceStyle {
+    /// `} else {` on one line.
+    AlwaysSameLine,
+    /// `}` and `else {` on separate lines.
+    ClosingNextLine,
+    /// Every brace on a line of its own, like `BraceStyle::AlwaysNextLine`.
+    AlwaysNextLine,
+}
+
+/// Layout of a where-clause.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WhereStyle {
+    /// `where` on a line of its own, followed by one indented predicate per
+    /// line.
+    Vertical,
+    /// `where` indented on the line after the signature, followed by the
+    /// first predicate, with the remaining predicates aligned under it.
+    Legacy,
+}
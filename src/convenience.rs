@@ -1,5 +1,6 @@
-use crate::algorithm::{self, BeginToken, BreakToken, Breaks, Printer};
-use std::borrow::Cow;
+use crate::algorithm::{self, BeginToken, BreakToken, Breaks, Printer, Text};
+use crate::config::TrailingComma;
+use core::fmt::Display;
 
 impl Printer {
     pub fn ibox(&mut self, indent: isize) {
@@ -20,11 +21,24 @@ impl Printer {
         self.scan_end();
     }
 
-    pub fn word<S: Into<Cow<'static, str>>>(&mut self, wrd: S) {
+    // Ends a box, breaking it if its contents are wider than the given limit
+    // even though they would fit within the margin.
+    pub fn end_with_width(&mut self, max: Option<usize>) {
+        match max {
+            Some(max) => self.end_with_max_width(max as isize),
+            None => self.end(),
+        }
+    }
+
+    pub fn word<S: Into<Text>>(&mut self, wrd: S) {
         let s = wrd.into();
         self.scan_string(s);
     }
 
+    pub fn word_display<T: Display>(&mut self, value: &T) {
+        self.scan_string(Text::display(value));
+    }
+
     fn spaces(&mut self, n: usize) {
         self.scan_break(BreakToken {
             blank_space: n,
@@ -65,27 +79,49 @@ impl Printer {
     }
 
     pub fn trailing_comma(&mut self, is_last: bool) {
-        if is_last {
-            self.scan_break(BreakToken {
-                pre_break: Some(','),
-                ..BreakToken::default()
-            });
-        } else {
+        if !is_last {
             self.word(",");
             self.space();
+            return;
+        }
+        match self.config.trailing_comma {
+            TrailingComma::Vertical => self.scan_break(BreakToken {
+                pre_break: Some(','),
+                ..BreakToken::default()
+            }),
+            TrailingComma::Always => {
+                self.word(",");
+                self.zerobreak();
+            }
+            TrailingComma::Never => self.zerobreak(),
         }
     }
 
     pub fn trailing_comma_or_space(&mut self, is_last: bool) {
-        if is_last {
-            self.scan_break(BreakToken {
+        if !is_last {
+            self.word(",");
+            self.space();
+            return;
+        }
+        match self.config.trailing_comma {
+            TrailingComma::Vertical => self.scan_break(BreakToken {
                 blank_space: 1,
                 pre_break: Some(','),
                 ..BreakToken::default()
-            });
-        } else {
+            }),
+            TrailingComma::Always => {
+                self.word(",");
+                self.space();
+            }
+            TrailingComma::Never => self.space(),
+        }
+    }
+
+    // The comma after an element of a list that is always printed one
+    // element per line, like the fields of a struct.
+    pub fn vertical_comma(&mut self, is_last: bool) {
+        if !is_last || self.config.trailing_comma != TrailingComma::Never {
             self.word(",");
-            self.space();
         }
     }
 
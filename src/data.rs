@@ -3,6 +3,7 @@ use crate::fixup::FixupContext;
 use crate::iter::IterDelimited;
 use crate::path::PathKind;
 use crate::INDENT;
+use alloc::string::ToString;
 use syn::{Field, Fields, FieldsUnnamed, Variant, VisRestricted, Visibility};
 
 impl Printer {
@@ -20,7 +21,7 @@ impl Printer {
                     self.trailing_comma_or_space(field.is_last);
                 }
                 self.offset(-INDENT);
-                self.end();
+                self.end_with_width(self.config.struct_variant_width);
                 self.word("}");
             }
             Fields::Unnamed(fields) => {
@@ -73,7 +74,7 @@ impl Printer {
         if !omit_in {
             self.word("in ");
         }
-        self.path(&vis.path, PathKind::Simple);
+        self.path_skip(&vis.path, 0, PathKind::Simple);
         self.word(") ");
     }
 }
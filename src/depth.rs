@@ -0,0 +1,56 @@
+use crate::algorithm::Printer;
+use core::fmt::{self, Display};
+#[cfg(feature = "std")]
+use std::error::Error;
+
+impl Printer {
+    // Enters a nested expression. Returns false, after recording the error,
+    // if that goes past the configured limit, in which case the caller must
+    // print nothing and not call `leave`.
+    pub(crate) fn enter(&mut self) -> bool {
+        if Some(self.depth) == self.config.max_depth {
+            self.too_deep = true;
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    pub(crate) fn depth_error(&self) -> Option<DepthError> {
+        match self.config.max_depth {
+            Some(max_depth) if self.too_deep => Some(DepthError { max_depth }),
+            _ => None,
+        }
+    }
+}
+
+/// Error returned by [`try_unparse_with_config`][crate::try_unparse_with_config]
+/// when expressions are nested deeper than [`Config::max_depth`][crate::Config::max_depth].
+#[derive(Debug)]
+pub struct DepthError {
+    max_depth: usize,
+}
+
+impl DepthError {
+    /// The limit that was exceeded.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+}
+
+impl Display for DepthError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "expressions are nested more than {} levels deep",
+            self.max_depth,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DepthError {}
@@ -1,12 +1,16 @@
-use crate::algorithm::{BreakToken, Printer};
+use crate::algorithm::{BreakToken, Printer, Text};
 use crate::attr;
 use crate::classify;
+use crate::config::{ControlBraceStyle, TrailingComma};
 use crate::fixup::FixupContext;
 use crate::iter::IterDelimited;
 use crate::path::PathKind;
 use crate::precedence::Precedence;
 use crate::stmt;
 use crate::INDENT;
+use alloc::format;
+use alloc::vec::Vec;
+use core::{cmp, iter};
 use proc_macro2::TokenStream;
 use syn::punctuated::Punctuated;
 use syn::{
@@ -15,12 +19,16 @@ use syn::{
     ExprField, ExprForLoop, ExprGroup, ExprIf, ExprIndex, ExprInfer, ExprLet, ExprLit, ExprLoop,
     ExprMacro, ExprMatch, ExprMethodCall, ExprParen, ExprPath, ExprRange, ExprRawAddr,
     ExprReference, ExprRepeat, ExprReturn, ExprStruct, ExprTry, ExprTryBlock, ExprTuple, ExprUnary,
-    ExprUnsafe, ExprWhile, ExprYield, FieldValue, Index, Label, Member, PointerMutability,
+    ExprUnsafe, ExprWhile, ExprYield, FieldValue, Index, Label, Lit, Member, PointerMutability,
     RangeLimits, ReturnType, Stmt, Token, UnOp,
 };
 
 impl Printer {
     pub fn expr(&mut self, expr: &Expr, mut fixup: FixupContext) {
+        if !self.enter() {
+            return;
+        }
+
         let needs_paren = fixup.parenthesize(expr);
         if needs_paren {
             self.word("(");
@@ -40,7 +48,7 @@ impl Printer {
             Expr::Break(expr) => self.expr_break(expr, fixup),
             Expr::Call(expr) => self.expr_call(expr, beginning_of_line, fixup),
             Expr::Cast(expr) => self.expr_cast(expr, fixup),
-            Expr::Closure(expr) => self.expr_closure(expr, fixup),
+            Expr::Closure(expr) => self.expr_closure(expr, fixup, None),
             Expr::Const(expr) => self.expr_const(expr),
             Expr::Continue(expr) => self.expr_continue(expr),
             Expr::Field(expr) => self.expr_field(expr, beginning_of_line, fixup),
@@ -77,6 +85,8 @@ impl Printer {
         if needs_paren {
             self.word(")");
         }
+
+        self.leave();
     }
 
     pub fn expr_beginning_of_line(
@@ -86,6 +96,10 @@ impl Printer {
         beginning_of_line: bool,
         mut fixup: FixupContext,
     ) {
+        if !self.enter() {
+            return;
+        }
+
         needs_paren |= fixup.parenthesize(expr);
         if needs_paren {
             self.word("(");
@@ -104,47 +118,88 @@ impl Printer {
         if needs_paren {
             self.word(")");
         }
+
+        self.leave();
     }
 
+    // Prints the receiver of a postfix operator. A chain like `a.b().c()?` is
+    // walked from the outside in with a loop rather than by recursion, so that
+    // very long chains do not overflow the stack.
     fn prefix_subexpr(
         &mut self,
-        expr: &Expr,
+        mut expr: &Expr,
         mut needs_paren: bool,
-        beginning_of_line: bool,
+        mut beginning_of_line: bool,
         mut fixup: FixupContext,
     ) {
-        needs_paren |= fixup.parenthesize(expr);
-        if needs_paren {
-            self.word("(");
-            fixup = FixupContext::NONE;
-        }
-
-        match expr {
-            Expr::Await(expr) => self.prefix_subexpr_await(expr, beginning_of_line, fixup),
-            Expr::Call(expr) => self.prefix_subexpr_call(expr, fixup),
-            Expr::Field(expr) => self.prefix_subexpr_field(expr, beginning_of_line, fixup),
-            Expr::Index(expr) => self.prefix_subexpr_index(expr, beginning_of_line, fixup),
-            Expr::MethodCall(expr) => {
-                let unindent_call_args = false;
-                self.prefix_subexpr_method_call(expr, beginning_of_line, unindent_call_args, fixup);
+        let mut chain = Vec::new();
+        loop {
+            needs_paren |= fixup.parenthesize(expr);
+            if needs_paren {
+                self.word("(");
+                fixup = FixupContext::NONE;
             }
-            Expr::Try(expr) => self.prefix_subexpr_try(expr, beginning_of_line, fixup),
-            _ => {
-                self.cbox(-INDENT);
-                self.expr(expr, fixup);
-                self.end();
+
+            let (operand, operand_needs_paren, operand_fixup) = match postfix_operand(expr, fixup) {
+                Some(operand) => operand,
+                None => {
+                    self.cbox(-INDENT);
+                    self.expr(expr, fixup);
+                    self.end();
+                    if needs_paren {
+                        self.word(")");
+                    }
+                    break;
+                }
+            };
+            chain.push((expr, needs_paren, beginning_of_line));
+            if let Expr::Call(_) = expr {
+                beginning_of_line = false;
             }
+            expr = operand;
+            needs_paren = operand_needs_paren;
+            fixup = operand_fixup;
         }
 
-        if needs_paren {
-            self.word(")");
+        while let Some((expr, needs_paren, beginning_of_line)) = chain.pop() {
+            match expr {
+                Expr::Await(expr) => self.await_suffix(expr, beginning_of_line),
+                Expr::Call(expr) => {
+                    self.word("(");
+                    self.call_args(&expr.args, None);
+                    self.word(")");
+                }
+                Expr::Field(expr) => self.field_suffix(expr, beginning_of_line),
+                Expr::Index(expr) => {
+                    self.word("[");
+                    self.expr(&expr.index, FixupContext::NONE);
+                    self.word("]");
+                }
+                Expr::MethodCall(expr) => {
+                    let unindent_call_args = false;
+                    let chain_boxes = None;
+                    self.method_call_suffix(
+                        expr,
+                        beginning_of_line,
+                        unindent_call_args,
+                        chain_boxes,
+                    );
+                }
+                Expr::Try(_) => self.word("?"),
+                _ => unreachable!(),
+            }
+            if needs_paren {
+                self.word(")");
+            }
         }
     }
 
     fn expr_condition(&mut self, expr: &Expr) {
         self.cbox(0);
         self.expr(expr, FixupContext::new_condition());
-        if needs_newline_if_wrap(expr) {
+        if self.config.control_brace_style == ControlBraceStyle::AlwaysNextLine {
+            // The caller puts the brace on the next line.
+        } else if needs_newline_if_wrap(expr) {
             self.space();
         } else {
             self.nbsp();
@@ -152,6 +207,37 @@ impl Printer {
         self.end();
     }
 
+    // Between the condition of a control flow expression and the opening
+    // brace of a body that is broken over multiple lines, unless empty.
+    fn control_brace(&mut self, nonempty: bool) {
+        if self.config.control_brace_style == ControlBraceStyle::AlwaysNextLine {
+            if nonempty {
+                self.hardbreak();
+            } else {
+                self.nbsp();
+            }
+        }
+    }
+
+    // Between the condition of an `if` or `else if` and the opening brace of
+    // a body that breaks along with the rest of the if-else.
+    fn control_brace_consistent(&mut self) {
+        if self.config.control_brace_style == ControlBraceStyle::AlwaysNextLine {
+            self.space();
+            self.offset(-INDENT);
+        }
+    }
+
+    // Between `else` and the opening brace of its body.
+    fn else_brace(&mut self) {
+        if self.config.control_brace_style == ControlBraceStyle::AlwaysNextLine {
+            self.space();
+            self.offset(-INDENT);
+        } else {
+            self.nbsp();
+        }
+    }
+
     pub fn subexpr(&mut self, expr: &Expr, needs_paren: bool, mut fixup: FixupContext) {
         if needs_paren {
             self.word("(");
@@ -167,15 +253,49 @@ impl Printer {
 
     fn expr_array(&mut self, expr: &ExprArray) {
         self.outer_attrs(&expr.attrs);
+        self.array_elements(expr.elems.iter());
+    }
+
+    pub fn array_elements<'a>(&mut self, elems: impl Iterator<Item = &'a Expr> + Clone) {
         self.word("[");
         self.cbox(INDENT);
         self.zerobreak();
-        for element in expr.elems.iter().delimited() {
-            self.expr(&element, FixupContext::NONE);
-            self.trailing_comma(element.is_last);
+        let hex_digits = if self.config.hex_int_tables {
+            hex_digits(elems.clone())
+        } else {
+            None
+        };
+        let mut iter = elems.clone().peekable();
+        let dense = hex_digits.is_some() || iter.peek().is_some() && iter.all(is_short_element);
+        if dense {
+            // Fill each line with as many elements as fit, rather than one
+            // element per line, so that lookup tables stay compact.
+            self.ibox(0);
+            for element in elems.delimited() {
+                match (hex_digits, *element) {
+                    (
+                        Some(digits),
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Int(lit), ..
+                        }),
+                    ) => self.lit_int_hex(lit, digits),
+                    _ => self.expr(&element, FixupContext::NONE),
+                }
+                if !element.is_last {
+                    self.word(",");
+                    self.space();
+                }
+            }
+            self.end();
+            self.trailing_comma(true);
+        } else {
+            for element in elems.delimited() {
+                self.expr(&element, FixupContext::NONE);
+                self.trailing_comma(element.is_last);
+            }
         }
         self.offset(-INDENT);
-        self.end();
+        self.end_with_width(self.config.array_width);
         self.word("]");
     }
 
@@ -209,75 +329,114 @@ impl Printer {
     }
 
     fn expr_await(&mut self, expr: &ExprAwait, beginning_of_line: bool, fixup: FixupContext) {
-        self.outer_attrs(&expr.attrs);
-        self.cbox(INDENT);
-        self.prefix_subexpr_await(expr, beginning_of_line, fixup);
-        self.end();
-    }
-
-    fn prefix_subexpr_await(
-        &mut self,
-        expr: &ExprAwait,
-        beginning_of_line: bool,
-        fixup: FixupContext,
-    ) {
         let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_dot(&expr.base);
 
+        self.outer_attrs(&expr.attrs);
+        self.cbox(INDENT);
         self.prefix_subexpr(
             &expr.base,
             left_prec < Precedence::Unambiguous,
             beginning_of_line,
             left_fixup,
         );
+        self.await_suffix(expr, beginning_of_line);
+        self.end_chain(&expr.base);
+    }
+
+    fn await_suffix(&mut self, expr: &ExprAwait, beginning_of_line: bool) {
         if !(beginning_of_line && is_short_ident(&expr.base)) {
+            let no_break = self.ends_with_recorded('.').then_some(' ');
             self.scan_break(BreakToken {
-                no_break: self.ends_with('.').then_some(' '),
+                no_break,
                 ..BreakToken::default()
             });
         }
         self.word(".await");
     }
 
-    fn expr_binary(&mut self, expr: &ExprBinary, fixup: FixupContext) {
-        let binop_prec = Precedence::of_binop(&expr.op);
-        let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_operator(
-            &expr.left,
-            match &expr.op {
-                BinOp::Sub(_)
-                | BinOp::Mul(_)
-                | BinOp::And(_)
-                | BinOp::Or(_)
-                | BinOp::BitAnd(_)
-                | BinOp::BitOr(_)
-                | BinOp::Shl(_)
-                | BinOp::Lt(_) => true,
-                _ => false,
-            },
-            match &expr.op {
-                BinOp::Shl(_) | BinOp::Lt(_) => true,
-                _ => false,
-            },
-            binop_prec,
-        );
-        let left_needs_group = match binop_prec {
-            Precedence::Assign => left_prec <= Precedence::Range,
-            Precedence::Compare => left_prec <= binop_prec,
-            _ => left_prec < binop_prec,
+    fn expr_binary(&mut self, mut expr: &ExprBinary, mut fixup: FixupContext) {
+        // A chain like `a + b + c` nests to the left. It is walked from the
+        // outside in with a loop rather than by recursion, so that very long
+        // chains do not overflow the stack.
+        //
+        // A let-chain like `let Some(x) = a && b` is laid out either on one
+        // line or with one operand per line, each after a leading `&&`.
+        let let_chain = is_let_chain(expr);
+        if let_chain {
+            self.cbox(INDENT);
+        }
+        let mut chain = Vec::new();
+        let (innermost, left, left_needs_group, left_fixup) = loop {
+            let binop_prec = Precedence::of_binop(&expr.op);
+            let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_operator(
+                &expr.left,
+                match &expr.op {
+                    BinOp::Sub(_)
+                    | BinOp::Mul(_)
+                    | BinOp::And(_)
+                    | BinOp::Or(_)
+                    | BinOp::BitAnd(_)
+                    | BinOp::BitOr(_)
+                    | BinOp::Shl(_)
+                    | BinOp::Lt(_) => true,
+                    _ => false,
+                },
+                match &expr.op {
+                    BinOp::Shl(_) | BinOp::Lt(_) => true,
+                    _ => false,
+                },
+                binop_prec,
+            );
+            let left_needs_group = match binop_prec {
+                Precedence::Assign => left_prec <= Precedence::Range,
+                Precedence::Compare => left_prec <= binop_prec,
+                _ => left_prec < binop_prec,
+            };
+            let right_fixup = fixup.rightmost_subexpression_fixup(false, false, binop_prec);
+            let right_needs_group = binop_prec != Precedence::Assign
+                && right_fixup.rightmost_subexpression_precedence(&expr.right) <= binop_prec;
+
+            self.outer_attrs(&expr.attrs);
+            if !let_chain {
+                self.ibox(INDENT);
+                self.ibox(-INDENT);
+            }
+
+            match &*expr.left {
+                Expr::Binary(left)
+                    if !left_needs_group
+                        && !left_fixup.parenthesize(&expr.left)
+                        && (!let_chain || matches!(left.op, BinOp::And(_))) =>
+                {
+                    chain.push((expr, right_needs_group, right_fixup));
+                    expr = left;
+                    fixup = left_fixup;
+                }
+                left => {
+                    let innermost = (expr, right_needs_group, right_fixup);
+                    break (innermost, left, left_needs_group, left_fixup);
+                }
+            }
         };
-        let right_fixup = fixup.rightmost_subexpression_fixup(false, false, binop_prec);
-        let right_needs_group = binop_prec != Precedence::Assign
-            && right_fixup.rightmost_subexpression_precedence(&expr.right) <= binop_prec;
 
-        self.outer_attrs(&expr.attrs);
-        self.ibox(INDENT);
-        self.ibox(-INDENT);
-        self.subexpr(&expr.left, left_needs_group, left_fixup);
-        self.end();
-        self.space();
-        self.binary_operator(&expr.op);
-        self.nbsp();
-        self.subexpr(&expr.right, right_needs_group, right_fixup);
-        self.end();
+        self.subexpr(left, left_needs_group, left_fixup);
+        for (expr, right_needs_group, right_fixup) in
+            iter::once(innermost).chain(chain.into_iter().rev())
+        {
+            if !let_chain {
+                self.end();
+            }
+            self.space();
+            self.binary_operator(&expr.op);
+            self.nbsp();
+            self.subexpr(&expr.right, right_needs_group, right_fixup);
+            if !let_chain {
+                self.end();
+            }
+        }
+        if let_chain {
+            self.end();
+        }
     }
 
     pub fn expr_block(&mut self, expr: &ExprBlock) {
@@ -308,42 +467,12 @@ impl Printer {
     }
 
     fn expr_call(&mut self, expr: &ExprCall, beginning_of_line: bool, fixup: FixupContext) {
-        let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_operator(
-            &expr.func,
-            true,
-            false,
-            Precedence::Unambiguous,
-        );
-        let needs_paren = if let Expr::Field(func) = &*expr.func {
-            matches!(func.member, Member::Named(_))
-        } else {
-            left_prec < Precedence::Unambiguous
-        };
+        let (needs_paren, left_fixup) = call_operand(expr, fixup);
 
         self.outer_attrs(&expr.attrs);
         self.expr_beginning_of_line(&expr.func, needs_paren, beginning_of_line, left_fixup);
         self.word("(");
-        self.call_args(&expr.args);
-        self.word(")");
-    }
-
-    fn prefix_subexpr_call(&mut self, expr: &ExprCall, fixup: FixupContext) {
-        let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_operator(
-            &expr.func,
-            true,
-            false,
-            Precedence::Unambiguous,
-        );
-        let needs_paren = if let Expr::Field(func) = &*expr.func {
-            matches!(func.member, Member::Named(_))
-        } else {
-            left_prec < Precedence::Unambiguous
-        };
-
-        let beginning_of_line = false;
-        self.prefix_subexpr(&expr.func, needs_paren, beginning_of_line, left_fixup);
-        self.word("(");
-        self.call_args(&expr.args);
+        self.call_args(&expr.args, Some(0));
         self.word(")");
     }
 
@@ -362,7 +491,15 @@ impl Printer {
         self.end();
     }
 
-    fn expr_closure(&mut self, expr: &ExprClosure, fixup: FixupContext) {
+    // If the closure is an overflowing argument, the number of enclosing boxes
+    // to keep on one line with its parameters while its body spans multiple
+    // lines.
+    fn expr_closure(
+        &mut self,
+        expr: &ExprClosure,
+        fixup: FixupContext,
+        overflow_boxes: Option<usize>,
+    ) {
         self.outer_attrs(&expr.attrs);
         self.ibox(0);
         if let Some(bound_lifetimes) = &expr.lifetimes {
@@ -405,6 +542,11 @@ impl Printer {
                     }
                     body => !is_blocklike(body),
                 };
+                // A body that is only braced when it breaks does not overflow,
+                // so that it stays unbraced wherever it fits on a line.
+                if let Some(boxes) = overflow_boxes.filter(|_| !wrap_in_brace) {
+                    self.measure_to_next_break(1 + boxes);
+                }
                 if wrap_in_brace {
                     self.cbox(INDENT);
                     let okay_to_brace = parseable_as_stmt(&expr.body);
@@ -441,6 +583,9 @@ impl Printer {
                 self.ty(ty);
                 self.nbsp();
                 self.neverbreak();
+                if let Some(boxes) = overflow_boxes {
+                    self.measure_to_next_break(1 + boxes);
+                }
                 if matches!(&*expr.body, Expr::Block(body) if body.attrs.is_empty() && body.label.is_none())
                 {
                     self.expr(
@@ -475,29 +620,25 @@ impl Printer {
     }
 
     fn expr_field(&mut self, expr: &ExprField, beginning_of_line: bool, fixup: FixupContext) {
-        self.outer_attrs(&expr.attrs);
-        self.cbox(INDENT);
-        self.prefix_subexpr_field(expr, beginning_of_line, fixup);
-        self.end();
-    }
-
-    fn prefix_subexpr_field(
-        &mut self,
-        expr: &ExprField,
-        beginning_of_line: bool,
-        fixup: FixupContext,
-    ) {
         let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_dot(&expr.base);
 
+        self.outer_attrs(&expr.attrs);
+        self.cbox(INDENT);
         self.prefix_subexpr(
             &expr.base,
             left_prec < Precedence::Unambiguous,
             beginning_of_line,
             left_fixup,
         );
+        self.field_suffix(expr, beginning_of_line);
+        self.end_chain(&expr.base);
+    }
+
+    fn field_suffix(&mut self, expr: &ExprField, beginning_of_line: bool) {
         if !(beginning_of_line && is_short_ident(&expr.base)) {
+            let no_break = self.ends_with_recorded('.').then_some(' ');
             self.scan_break(BreakToken {
-                no_break: self.ends_with('.').then_some(' '),
+                no_break,
                 ..BreakToken::default()
             });
         }
@@ -516,6 +657,7 @@ impl Printer {
         self.word(" in ");
         self.neverbreak();
         self.expr_condition(&expr.expr);
+        self.control_brace(!expr.body.stmts.is_empty() || attr::has_inner(&expr.attrs));
         self.word("{");
         self.neverbreak();
         self.cbox(INDENT);
@@ -544,15 +686,23 @@ impl Printer {
         self.end();
         if let Some((_else_token, else_branch)) = &expr.else_branch {
             let mut else_branch = &**else_branch;
+            self.control_brace_consistent();
             self.small_block(&expr.then_branch, &[]);
             loop {
-                self.word(" else ");
+                if self.config.control_brace_style == ControlBraceStyle::AlwaysSameLine {
+                    self.nbsp();
+                } else {
+                    self.space();
+                    self.offset(-INDENT);
+                }
+                self.word("else");
                 match else_branch {
                     Expr::If(expr) => {
-                        self.word("if ");
+                        self.word(" if ");
                         self.cbox(-INDENT);
                         self.expr_condition(&expr.cond);
                         self.end();
+                        self.control_brace_consistent();
                         self.small_block(&expr.then_branch, &[]);
                         if let Some((_else_token, next)) = &expr.else_branch {
                             else_branch = next;
@@ -560,17 +710,23 @@ impl Printer {
                         }
                     }
                     Expr::Block(expr) => {
+                        self.else_brace();
                         self.small_block(&expr.block, &[]);
                     }
                     // If not one of the valid expressions to exist in an else
                     // clause, wrap in a block.
-                    other => self.expr_as_small_block(other, INDENT),
+                    other => {
+                        self.else_brace();
+                        self.expr_as_small_block(other, INDENT);
+                    }
                 }
                 break;
             }
         } else if expr.then_branch.stmts.is_empty() {
+            self.control_brace(false);
             self.word("{}");
         } else {
+            self.control_brace_consistent();
             self.word("{");
             self.hardbreak();
             for stmt in expr.then_branch.stmts.iter().delimited() {
@@ -579,7 +735,11 @@ impl Printer {
             self.offset(-INDENT);
             self.word("}");
         }
-        self.end();
+        if expr.else_branch.is_some() {
+            self.end_with_width(self.config.single_line_if_else_max_width);
+        } else {
+            self.end();
+        }
     }
 
     fn expr_index(&mut self, expr: &ExprIndex, beginning_of_line: bool, fixup: FixupContext) {
@@ -602,30 +762,6 @@ impl Printer {
         self.word("]");
     }
 
-    fn prefix_subexpr_index(
-        &mut self,
-        expr: &ExprIndex,
-        beginning_of_line: bool,
-        fixup: FixupContext,
-    ) {
-        let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_operator(
-            &expr.expr,
-            true,
-            false,
-            Precedence::Unambiguous,
-        );
-
-        self.prefix_subexpr(
-            &expr.expr,
-            left_prec < Precedence::Unambiguous,
-            beginning_of_line,
-            left_fixup,
-        );
-        self.word("[");
-        self.expr(&expr.index, FixupContext::NONE);
-        self.word("]");
-    }
-
     fn expr_infer(&mut self, expr: &ExprInfer) {
         self.outer_attrs(&expr.attrs);
         self.word("_");
@@ -658,7 +794,15 @@ impl Printer {
         if let Some(label) = &expr.label {
             self.label(label);
         }
-        self.word("loop {");
+        self.word("loop");
+        if self.config.control_brace_style == ControlBraceStyle::AlwaysNextLine
+            && (!expr.body.stmts.is_empty() || attr::has_inner(&expr.attrs))
+        {
+            self.hardbreak();
+        } else {
+            self.nbsp();
+        }
+        self.word("{");
         self.cbox(INDENT);
         self.hardbreak_if_nonempty();
         self.inner_attrs(&expr.attrs);
@@ -681,13 +825,14 @@ impl Printer {
         self.ibox(0);
         self.word("match ");
         self.expr_condition(&expr.expr);
+        self.control_brace(!expr.arms.is_empty() || attr::has_inner(&expr.attrs));
         self.word("{");
         self.neverbreak();
         self.cbox(INDENT);
         self.hardbreak_if_nonempty();
         self.inner_attrs(&expr.attrs);
-        for arm in &expr.arms {
-            self.arm(arm);
+        for arm in expr.arms.iter().delimited() {
+            self.arm(&arm, arm.is_last);
             self.hardbreak();
         }
         self.offset(-INDENT);
@@ -702,31 +847,49 @@ impl Printer {
         beginning_of_line: bool,
         fixup: FixupContext,
     ) {
+        let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_dot(&expr.receiver);
+
         self.outer_attrs(&expr.attrs);
         self.cbox(INDENT);
         let unindent_call_args = beginning_of_line && is_short_ident(&expr.receiver);
-        self.prefix_subexpr_method_call(expr, beginning_of_line, unindent_call_args, fixup);
-        self.end();
+        self.prefix_subexpr(
+            &expr.receiver,
+            left_prec < Precedence::Unambiguous,
+            beginning_of_line,
+            left_fixup,
+        );
+        let chain_boxes = Some(1);
+        self.method_call_suffix(expr, beginning_of_line, unindent_call_args, chain_boxes);
+        self.end_chain(&expr.receiver);
+    }
+
+    // Ends the box around a chain of `.` links, the last of which is applied
+    // to the given base. Chains beyond the configured width or number of
+    // links are broken so that every link is on its own line, even if they
+    // would fit within the margin.
+    fn end_chain(&mut self, base: &Expr) {
+        let too_long = self
+            .config
+            .chain_links
+            .map_or(false, |max| 1 + chain_links(base) > max);
+        if too_long {
+            self.end_with_max_width(0);
+        } else {
+            self.end_with_width(self.config.chain_width);
+        }
     }
 
-    fn prefix_subexpr_method_call(
+    fn method_call_suffix(
         &mut self,
         expr: &ExprMethodCall,
         beginning_of_line: bool,
         unindent_call_args: bool,
-        fixup: FixupContext,
+        chain_boxes: Option<usize>,
     ) {
-        let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_dot(&expr.receiver);
-
-        self.prefix_subexpr(
-            &expr.receiver,
-            left_prec < Precedence::Unambiguous,
-            beginning_of_line,
-            left_fixup,
-        );
         if !(beginning_of_line && is_short_ident(&expr.receiver)) {
+            let no_break = self.ends_with_recorded('.').then_some(' ');
             self.scan_break(BreakToken {
-                no_break: self.ends_with('.').then_some(' '),
+                no_break,
                 ..BreakToken::default()
             });
         }
@@ -737,7 +900,7 @@ impl Printer {
         }
         self.cbox(if unindent_call_args { -INDENT } else { 0 });
         self.word("(");
-        self.call_args(&expr.args);
+        self.call_args(&expr.args, chain_boxes.map(|boxes| 1 + boxes));
         self.word(")");
         self.end();
     }
@@ -760,7 +923,7 @@ impl Printer {
             let (left_prec, left_fixup) =
                 fixup.leftmost_subexpression_with_operator(start, true, false, Precedence::Range);
             self.subexpr(start, left_prec <= Precedence::Range, left_fixup);
-        } else if self.ends_with('.') {
+        } else if self.ends_with_recorded('.') {
             self.nbsp();
         }
         self.word(match expr.limits {
@@ -836,7 +999,7 @@ impl Printer {
             self.space();
         }
         self.offset(-INDENT);
-        self.end_with_max_width(34);
+        self.end_with_width(self.config.struct_lit_width);
         self.word("}");
     }
 
@@ -853,18 +1016,6 @@ impl Printer {
         self.word("?");
     }
 
-    fn prefix_subexpr_try(&mut self, expr: &ExprTry, beginning_of_line: bool, fixup: FixupContext) {
-        let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_dot(&expr.expr);
-
-        self.prefix_subexpr(
-            &expr.expr,
-            left_prec < Precedence::Unambiguous,
-            beginning_of_line,
-            left_fixup,
-        );
-        self.word("?");
-    }
-
     fn expr_try_block(&mut self, expr: &ExprTryBlock) {
         self.outer_attrs(&expr.attrs);
         self.word("try ");
@@ -912,7 +1063,7 @@ impl Printer {
     #[cfg(not(feature = "verbatim"))]
     fn expr_verbatim(&mut self, expr: &TokenStream, _fixup: FixupContext) {
         if !expr.is_empty() {
-            unimplemented!("Expr::Verbatim `{}`", expr);
+            self.unsupported(format!("Expr::Verbatim `{}`", expr));
         }
     }
 
@@ -977,7 +1128,10 @@ impl Printer {
 
         let expr: ExprVerbatim = match syn::parse2(tokens.clone()) {
             Ok(expr) => expr,
-            Err(_) => unimplemented!("Expr::Verbatim `{}`", tokens),
+            Err(_) => {
+                self.unsupported(format!("Expr::Verbatim `{}`", tokens));
+                return;
+            }
         };
 
         match expr {
@@ -1021,6 +1175,7 @@ impl Printer {
         }
         self.word("while ");
         self.expr_condition(&expr.cond);
+        self.control_brace(!expr.body.stmts.is_empty() || attr::has_inner(&expr.attrs));
         self.word("{");
         self.neverbreak();
         self.cbox(INDENT);
@@ -1054,7 +1209,7 @@ impl Printer {
     fn field_value(&mut self, field_value: &FieldValue) {
         self.outer_attrs(&field_value.attrs);
         self.member(&field_value.member);
-        if field_value.colon_token.is_some() {
+        if field_value.colon_token.is_some() || self.member_is_renamed(&field_value.member) {
             self.word(": ");
             self.ibox(0);
             self.expr(&field_value.expr, FixupContext::NONE);
@@ -1062,13 +1217,25 @@ impl Printer {
         }
     }
 
-    fn arm(&mut self, arm: &Arm) {
+    fn arm(&mut self, arm: &Arm, is_last: bool) {
         self.outer_attrs(&arm.attrs);
         self.ibox(0);
         self.pat(&arm.pat);
         if let Some((_if_token, guard)) = &arm.guard {
-            self.word(" if ");
-            self.expr(guard, FixupContext::NONE);
+            match guard.as_ref() {
+                Expr::Binary(chain) if is_let_chain(chain) => {
+                    self.space();
+                    self.offset(INDENT);
+                    self.ibox(INDENT);
+                    self.word("if ");
+                    self.expr(guard, FixupContext::NONE);
+                    self.end();
+                }
+                _ => {
+                    self.word(" if ");
+                    self.expr(guard, FixupContext::NONE);
+                }
+            }
         }
         self.word(" => ");
         let empty_block;
@@ -1111,7 +1278,15 @@ impl Printer {
             self.offset(-INDENT);
             self.end();
             self.word("}");
+            if self.config.trailing_comma == TrailingComma::Always {
+                self.word(",");
+            }
         } else {
+            let comma = match self.config.trailing_comma {
+                TrailingComma::Vertical => classify::requires_comma_to_be_match_arm(body),
+                TrailingComma::Always => true,
+                TrailingComma::Never => !is_last && classify::requires_comma_to_be_match_arm(body),
+            };
             self.neverbreak();
             self.cbox(INDENT);
             let okay_to_brace = parseable_as_stmt(body);
@@ -1123,8 +1298,13 @@ impl Printer {
             self.scan_break(BreakToken {
                 offset: -INDENT,
                 pre_break: (okay_to_brace && stmt::add_semi(body)).then_some(';'),
-                post_break: if okay_to_brace { "}" } else { ")," },
-                no_break: classify::requires_comma_to_be_match_arm(body).then_some(','),
+                post_break: match (okay_to_brace, self.config.trailing_comma) {
+                    (true, TrailingComma::Always) => "},",
+                    (true, _) => "}",
+                    (false, TrailingComma::Never) if is_last => ")",
+                    (false, _) => "),",
+                },
+                no_break: comma.then_some(','),
                 ..BreakToken::default()
             });
             self.end();
@@ -1132,25 +1312,48 @@ impl Printer {
         self.end();
     }
 
-    fn call_args(&mut self, args: &Punctuated<Expr, Token![,]>) {
+    // The enclosing boxes are those of the call that the arguments belong to,
+    // such as a method chain, which are kept on one line along with the
+    // arguments if the last argument overflows. None for a call in the middle
+    // of a chain, whose arguments do not overflow.
+    fn call_args(&mut self, args: &Punctuated<Expr, Token![,]>, enclosing_boxes: Option<usize>) {
+        let overflow = enclosing_boxes.filter(|_| {
+            self.config.overflow_last_arg && args.last().map_or(false, is_overflowable)
+        });
         let mut iter = args.iter();
         match (iter.next(), iter.next()) {
-            (Some(expr), None) if is_blocklike(expr) => {
-                self.expr(expr, FixupContext::NONE);
-            }
+            (Some(expr), None) if is_blocklike(expr) => match overflow {
+                Some(boxes) if boxes > 0 => self.overflowing_arg(expr, boxes),
+                _ => self.expr(expr, FixupContext::NONE),
+            },
             _ => {
                 self.cbox(INDENT);
                 self.zerobreak();
                 for arg in args.iter().delimited() {
-                    self.expr(&arg, FixupContext::NONE);
+                    match overflow {
+                        Some(boxes) if arg.is_last => self.overflowing_arg(&arg, 1 + boxes),
+                        _ => self.expr(&arg, FixupContext::NONE),
+                    }
                     self.trailing_comma(arg.is_last);
                 }
                 self.offset(-INDENT);
-                self.end();
+                self.end_with_width(self.config.fn_call_width);
             }
         }
     }
 
+    // Prints the last argument of a call such that the given number of
+    // enclosing boxes fit if the call fits on one line up to the opening of
+    // the argument's body, like `{`.
+    fn overflowing_arg(&mut self, expr: &Expr, boxes: usize) {
+        if let Expr::Closure(expr) = expr {
+            self.expr_closure(expr, FixupContext::NONE, Some(boxes));
+        } else {
+            self.measure_to_next_break(boxes);
+            self.expr(expr, FixupContext::NONE);
+        }
+    }
+
     pub fn small_block(&mut self, block: &Block, attrs: &[Attribute]) {
         self.word("{");
         if attr::has_inner(attrs) || !block.stmts.is_empty() {
@@ -1187,13 +1390,22 @@ impl Printer {
 
     pub fn member(&mut self, member: &Member) {
         match member {
-            Member::Named(ident) => self.ident(ident),
+            Member::Named(ident) => self.unhygienic_ident(ident),
             Member::Unnamed(index) => self.index(index),
         }
     }
 
+    // Whether a shorthand field `S { x }` needs to be written out as
+    // `S { x: x_1 }` because the local binding was renamed for hygiene.
+    pub(crate) fn member_is_renamed(&self, member: &Member) -> bool {
+        match member {
+            Member::Named(ident) => self.hygiene_rename(ident).is_some(),
+            Member::Unnamed(_) => false,
+        }
+    }
+
     fn index(&mut self, member: &Index) {
-        self.word(member.index.to_string());
+        self.word_display(&member.index);
     }
 
     fn binary_operator(&mut self, op: &BinOp) {
@@ -1253,6 +1465,23 @@ impl Printer {
     }
 }
 
+// Whether the operands joined by `&&` in this expression include a `let`.
+fn is_let_chain(mut expr: &ExprBinary) -> bool {
+    loop {
+        if !matches!(expr.op, BinOp::And(_)) {
+            return false;
+        }
+        if let Expr::Let(_) = &*expr.right {
+            return true;
+        }
+        match &*expr.left {
+            Expr::Let(_) => return true,
+            Expr::Binary(left) => expr = left,
+            _ => return false,
+        }
+    }
+}
+
 fn needs_newline_if_wrap(expr: &Expr) -> bool {
     match expr {
         #![cfg_attr(all(test, exhaustive), deny(non_exhaustive_omitted_patterns))]
@@ -1307,6 +1536,84 @@ fn needs_newline_if_wrap(expr: &Expr) -> bool {
     }
 }
 
+// The receiver of a postfix expression, whether it needs parentheses, and the
+// fixup to print it with.
+fn postfix_operand(expr: &Expr, fixup: FixupContext) -> Option<(&Expr, bool, FixupContext)> {
+    let (operand, (prec, fixup)) = match expr {
+        Expr::Await(expr) => (
+            &*expr.base,
+            fixup.leftmost_subexpression_with_dot(&expr.base),
+        ),
+        Expr::Call(expr) => {
+            let (needs_paren, fixup) = call_operand(expr, fixup);
+            return Some((&expr.func, needs_paren, fixup));
+        }
+        Expr::Field(expr) => (
+            &*expr.base,
+            fixup.leftmost_subexpression_with_dot(&expr.base),
+        ),
+        Expr::Index(expr) => (
+            &*expr.expr,
+            fixup.leftmost_subexpression_with_operator(
+                &expr.expr,
+                true,
+                false,
+                Precedence::Unambiguous,
+            ),
+        ),
+        Expr::MethodCall(expr) => (
+            &*expr.receiver,
+            fixup.leftmost_subexpression_with_dot(&expr.receiver),
+        ),
+        Expr::Try(expr) => (
+            &*expr.expr,
+            fixup.leftmost_subexpression_with_dot(&expr.expr),
+        ),
+        _ => return None,
+    };
+    Some((operand, prec < Precedence::Unambiguous, fixup))
+}
+
+// Number of `.` links in a chain of postfix operators.
+fn chain_links(mut expr: &Expr) -> usize {
+    let mut links = 0;
+    loop {
+        expr = match expr {
+            Expr::Await(expr) => {
+                links += 1;
+                &expr.base
+            }
+            Expr::Field(expr) => {
+                links += 1;
+                &expr.base
+            }
+            Expr::MethodCall(expr) => {
+                links += 1;
+                &expr.receiver
+            }
+            Expr::Call(expr) => &expr.func,
+            Expr::Index(expr) => &expr.expr,
+            Expr::Try(expr) => &expr.expr,
+            _ => return links,
+        };
+    }
+}
+
+fn call_operand(expr: &ExprCall, fixup: FixupContext) -> (bool, FixupContext) {
+    let (left_prec, left_fixup) = fixup.leftmost_subexpression_with_operator(
+        &expr.func,
+        true,
+        false,
+        Precedence::Unambiguous,
+    );
+    let needs_paren = if let Expr::Field(func) = &*expr.func {
+        matches!(func.member, Member::Named(_))
+    } else {
+        left_prec < Precedence::Unambiguous
+    };
+    (needs_paren, left_fixup)
+}
+
 fn is_short_ident(expr: &Expr) -> bool {
     if let Expr::Path(expr) = expr {
         return expr.attrs.is_empty()
@@ -1314,11 +1621,116 @@ fn is_short_ident(expr: &Expr) -> bool {
             && expr
                 .path
                 .get_ident()
-                .map_or(false, |ident| ident.to_string().len() as isize <= INDENT);
+                .map_or(false, |ident| Text::display(ident).len() as isize <= INDENT);
     }
     false
 }
 
+// Mirrors rustfmt's short_array_element_width_threshold.
+const SHORT_ELEMENT_WIDTH: usize = 10;
+
+fn is_short_element(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(expr) => expr.attrs.is_empty() && lit_width(&expr.lit) <= SHORT_ELEMENT_WIDTH,
+        Expr::Unary(ExprUnary {
+            attrs,
+            op: UnOp::Neg(_),
+            expr,
+        }) => match &**expr {
+            Expr::Lit(expr) => {
+                attrs.is_empty()
+                    && expr.attrs.is_empty()
+                    && lit_width(&expr.lit) < SHORT_ELEMENT_WIDTH
+            }
+            _ => false,
+        },
+        Expr::Path(expr) => {
+            if !expr.attrs.is_empty() || expr.qself.is_some() {
+                return false;
+            }
+            let mut width = 0;
+            if expr.path.leading_colon.is_some() {
+                width += 2;
+            }
+            for segment in expr.path.segments.iter().delimited() {
+                if !segment.arguments.is_none() {
+                    return false;
+                }
+                width += Text::display(&segment.ident).len();
+                if !segment.is_last {
+                    width += 2;
+                }
+            }
+            width <= SHORT_ELEMENT_WIDTH
+        }
+        _ => false,
+    }
+}
+
+// Arrays with fewer elements than this are not treated as tables, so that
+// short arrays like `&[0, 0]` keep their decimal literals.
+const MIN_TABLE_LEN: usize = 8;
+
+// Number of hexadecimal digits that every element fits in, rounded up to whole
+// bytes, if the array looks like a table: enough elements, all unattributed
+// integer literals with the same suffix.
+fn hex_digits<'a>(elems: impl Iterator<Item = &'a Expr>) -> Option<usize> {
+    let mut digits = None;
+    let mut suffix = None;
+    let mut len = 0;
+    for element in elems {
+        let lit = match element {
+            Expr::Lit(ExprLit {
+                attrs,
+                lit: Lit::Int(lit),
+            }) if attrs.is_empty() => lit,
+            _ => return None,
+        };
+        if *suffix.get_or_insert(lit.suffix()) != lit.suffix() {
+            return None;
+        }
+        let value: u128 = lit.base10_parse().ok()?;
+        let bytes = cmp::max((128 - value.leading_zeros() as usize + 7) / 8, 1);
+        digits = cmp::max(digits, Some(bytes * 2));
+        len += 1;
+    }
+    if len < MIN_TABLE_LEN {
+        return None;
+    }
+    digits
+}
+
+fn lit_width(lit: &Lit) -> usize {
+    match lit {
+        Lit::Str(lit) => Text::display(&lit.token()).len(),
+        Lit::ByteStr(lit) => Text::display(&lit.token()).len(),
+        Lit::CStr(lit) => Text::display(&lit.token()).len(),
+        Lit::Byte(lit) => Text::display(&lit.token()).len(),
+        Lit::Char(lit) => Text::display(&lit.token()).len(),
+        Lit::Int(lit) => Text::display(&lit.token()).len(),
+        Lit::Float(lit) => Text::display(&lit.token()).len(),
+        Lit::Bool(lit) => Text::display(&lit.token()).len(),
+        Lit::Verbatim(lit) => Text::display(lit).len(),
+        _ => usize::MAX,
+    }
+}
+
+fn is_overflowable(expr: &Expr) -> bool {
+    match expr {
+        // A body that is itself delimited, like a tuple, would overflow into
+        // a layout that does not look like a block.
+        Expr::Closure(ExprClosure { attrs, body, .. }) => {
+            !attr::has_outer(attrs)
+                && !matches!(
+                    **body,
+                    Expr::Array(_) | Expr::Closure(_) | Expr::Struct(_) | Expr::Tuple(_),
+                )
+        }
+        Expr::Match(ExprMatch { attrs, .. }) => !attr::has_outer(attrs),
+        _ => is_blocklike(expr),
+    }
+}
+
 fn is_blocklike(expr: &Expr) -> bool {
     match expr {
         #![cfg_attr(all(test, exhaustive), deny(non_exhaustive_omitted_patterns))]
@@ -1,17 +1,166 @@
 use crate::algorithm::Printer;
-use syn::File;
+#[cfg(feature = "rayon")]
+use crate::algorithm::Recorded;
+use crate::config::Config;
+use crate::depth::DepthError;
+#[cfg(feature = "hygiene")]
+use crate::hygiene;
+use crate::shorten::Shortening;
+use alloc::string::String;
+#[cfg(feature = "rayon")]
+use alloc::vec::Vec;
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use syn::{File, Item};
 
 impl Printer {
     pub fn file(&mut self, file: &File) {
         self.cbox(0);
+        self.file_header(file);
+        for item in &file.items {
+            self.top_level_item(item);
+        }
+        self.shortening = Shortening::default();
+        self.end();
+    }
+
+    fn file_header(&mut self, file: &File) {
         if let Some(shebang) = &file.shebang {
             self.word(shebang.clone());
             self.hardbreak();
         }
+        self.shortening = Shortening::root(&file.attrs, &file.items, &self.config);
         self.inner_attrs(&file.attrs);
-        for item in &file.items {
-            self.item(item);
+        self.shortened_imports();
+    }
+
+    fn top_level_item(&mut self, item: &Item) {
+        #[cfg(feature = "hygiene")]
+        if self.config.hygiene {
+            self.renames = hygiene::renames(item);
+        }
+        self.item(item);
+        self.renames.clear();
+    }
+}
+
+// Prints a whole file on the current thread into `out`, replacing its contents,
+// and leaves the printer ready to print the next one. On error `out` is left
+// unchanged.
+pub(crate) fn print(p: &mut Printer, file: &File, out: &mut String) -> Result<(), DepthError> {
+    p.file(file);
+    match p.depth_error() {
+        Some(error) => {
+            p.reset();
+            Err(error)
+        }
+        None => {
+            p.finish(out);
+            Ok(())
+        }
+    }
+}
+
+fn print_once(config: &Config, file: &File) -> Result<String, DepthError> {
+    let mut p = Printer::with_config(config.clone());
+    p.file(file);
+    match p.depth_error() {
+        Some(error) => Err(error),
+        None => Ok(p.eof()),
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn unparse(file: &File, config: &Config) -> Result<String, DepthError> {
+    print_once(config, file)
+}
+
+// Every top-level item begins on a fresh line at indentation 0, so items can
+// be laid out independently by separate printers and their output
+// concatenated, with the same result as printing the whole file at once.
+//
+// Syntax trees cannot be sent to other threads, so the main thread records
+// the printer operations for each item, and the layout happens in parallel.
+#[cfg(feature = "rayon")]
+pub(crate) fn unparse(file: &File, config: &Config) -> Result<String, DepthError> {
+    if rayon::current_num_threads() == 1 {
+        return print_once(config, file);
+    }
+
+    // The header is printed together with the first item, because how the
+    // break after an inner attribute is laid out depends on what follows it.
+    let mut header = Printer::with_config(config.clone());
+    header.cbox(0);
+    header.file_header(file);
+    let shortening = header.shortening.clone();
+    let rest = match file.items.split_first() {
+        Some((first, rest)) => {
+            header.top_level_item(first);
+            rest
+        }
+        None => &[],
+    };
+    header.end();
+    if let Some(error) = header.depth_error() {
+        return Err(error);
+    }
+
+    let print_item = |item: &Item, recording: Option<Vec<Recorded>>| {
+        let mut p = Printer::with_config(config.clone());
+        p.shortening = shortening.clone();
+        p.recording = recording;
+        p.cbox(0);
+        p.top_level_item(item);
+        p.end();
+        p
+    };
+
+    let recordings = rest
+        .iter()
+        .map(|item| {
+            let p = print_item(item, Some(Vec::new()));
+            match p.depth_error() {
+                Some(error) => Err(error),
+                None => Ok(p.recording.unwrap()),
+            }
+        })
+        .collect::<Result<Vec<Vec<Recorded>>, DepthError>>()?;
+
+    let items: Vec<Option<String>> = recordings
+        .into_par_iter()
+        .map(|recording| replay(config, recording))
+        .collect();
+
+    let mut out = header.eof();
+    for (item, printed) in rest.iter().zip(items) {
+        match printed {
+            Some(printed) => out.push_str(&printed),
+            None => out.push_str(&print_item(item, None).eof()),
+        }
+    }
+    Ok(out)
+}
+
+// Returns None if the layout turned out differently than what the recording
+// assumed, in which case the item needs to be printed again.
+#[cfg(feature = "rayon")]
+fn replay(config: &Config, recording: Vec<Recorded>) -> Option<String> {
+    let mut p = Printer::with_config(config.clone());
+    for recorded in recording {
+        match recorded {
+            Recorded::Begin(token) => p.scan_begin(token),
+            Recorded::End => p.scan_end(),
+            Recorded::Break(token) => p.scan_break(token),
+            Recorded::String(string) => p.scan_string(string),
+            Recorded::Offset(offset) => p.offset(offset),
+            Recorded::EndWithMaxWidth(max) => p.end_with_max_width(max),
+            Recorded::MeasureToNextBreak(boxes) => p.measure_to_next_break(boxes),
+            Recorded::EndsWith(ch, ends_with) => {
+                if p.ends_with(ch) != ends_with {
+                    return None;
+                }
+            }
         }
-        self.end();
     }
+    Some(p.eof())
 }
@@ -240,7 +240,7 @@ impl FixupContext {
 
     fn leftmost_subexpression_precedence(self, expr: &Expr) -> Precedence {
         if !self.next_operator_can_begin_expr || self.next_operator == Precedence::Range {
-            if let Scan::Bailout = scan_right(expr, self, Precedence::MIN, 0, 0) {
+            if let Scan::Bailout = scan_right(expr, self, Precedence::MIN, 0, 0, 0) {
                 if scan_left(expr, self) {
                     return Precedence::Unambiguous;
                 }
@@ -289,6 +289,10 @@ impl FixupContext {
     }
 
     pub fn rightmost_subexpression_precedence(self, expr: &Expr) -> Precedence {
+        self.rightmost_subexpression_precedence_at_depth(expr, 0)
+    }
+
+    fn rightmost_subexpression_precedence_at_depth(self, expr: &Expr, depth: usize) -> Precedence {
         let default_prec = self.precedence(expr);
 
         if match self.previous_operator {
@@ -300,7 +304,8 @@ impl FixupContext {
             Precedence::Range | Precedence::Or | Precedence::And => true,
             _ => !self.next_operator_can_begin_expr,
         } {
-            if let Scan::Bailout | Scan::Fail = scan_right(expr, self, self.previous_operator, 1, 0)
+            if let Scan::Bailout | Scan::Fail =
+                scan_right(expr, self, self.previous_operator, 1, 0, depth)
             {
                 if scan_left(expr, self) {
                     return Precedence::Prefix;
@@ -400,13 +405,24 @@ fn scan_left(expr: &Expr, fixup: FixupContext) -> bool {
     }
 }
 
+// How far down the right-hand side of nested operators to look, which keeps
+// pathologically deep expressions from overflowing the stack. At this depth the
+// scan answers Consume, as if the rest of the expression would swallow the next
+// operator. That never elides parentheses: the caller falls back to the
+// ordinary precedence of the expression, which parenthesizes at least as much.
+const MAX_SCAN_DEPTH: usize = 64;
+
 fn scan_right(
     expr: &Expr,
     fixup: FixupContext,
     precedence: Precedence,
     fail_offset: u8,
     bailout_offset: u8,
+    depth: usize,
 ) -> Scan {
+    if depth == MAX_SCAN_DEPTH {
+        return Scan::Consume;
+    }
     let consume_by_precedence = if match precedence {
         Precedence::Assign | Precedence::Compare => precedence <= fixup.next_operator,
         _ => precedence < fixup.next_operator,
@@ -438,6 +454,7 @@ fn scan_right(
                     _ => 1,
                 },
                 1,
+                depth + 1,
             );
             if let Scan::Bailout | Scan::Consume = scan {
                 Scan::Consume
@@ -471,6 +488,7 @@ fn scan_right(
                     _ => 1,
                 },
                 consume_by_precedence as u8 - Scan::Bailout as u8,
+                depth + 1,
             );
             match scan {
                 Scan::Fail => {}
@@ -478,7 +496,8 @@ fn scan_right(
                 Scan::Consume => return Scan::Consume,
             }
             let right_needs_group = binop_prec != Precedence::Assign
-                && right_fixup.rightmost_subexpression_precedence(&e.right) <= binop_prec;
+                && right_fixup.rightmost_subexpression_precedence_at_depth(&e.right, depth + 1)
+                    <= binop_prec;
             if right_needs_group {
                 consume_by_precedence
             } else if let (Scan::Fail, Precedence::Unambiguous) = (scan, fixup.next_operator) {
@@ -509,13 +528,16 @@ fn scan_right(
                     _ => 1,
                 },
                 consume_by_precedence as u8 - Scan::Bailout as u8,
+                depth + 1,
             );
             match scan {
                 Scan::Fail => {}
                 Scan::Bailout => return consume_by_precedence,
                 Scan::Consume => return Scan::Consume,
             }
-            if right_fixup.rightmost_subexpression_precedence(expr) < Precedence::Prefix {
+            if right_fixup.rightmost_subexpression_precedence_at_depth(expr, depth + 1)
+                < Precedence::Prefix
+            {
                 consume_by_precedence
             } else if let (Scan::Fail, Precedence::Unambiguous) = (scan, fixup.next_operator) {
                 Scan::Fail
@@ -539,6 +561,7 @@ fn scan_right(
                         Precedence::Assign | Precedence::Range => 0,
                         _ => 1,
                     },
+                    depth + 1,
                 );
                 if match (scan, fixup.next_operator) {
                     (Scan::Fail, _) => false,
@@ -547,7 +570,9 @@ fn scan_right(
                 } {
                     return Scan::Consume;
                 }
-                if right_fixup.rightmost_subexpression_precedence(end) <= Precedence::Range {
+                if right_fixup.rightmost_subexpression_precedence_at_depth(end, depth + 1)
+                    <= Precedence::Range
+                {
                     Scan::Consume
                 } else {
                     Scan::Fail
@@ -567,7 +592,7 @@ fn scan_right(
                     return Scan::Consume;
                 }
                 let right_fixup = fixup.rightmost_subexpression_fixup(true, true, Precedence::Jump);
-                match scan_right(value, right_fixup, Precedence::Jump, 1, 1) {
+                match scan_right(value, right_fixup, Precedence::Jump, 1, 1, depth + 1) {
                     Scan::Fail => Scan::Bailout,
                     Scan::Bailout | Scan::Consume => Scan::Consume,
                 }
@@ -584,7 +609,7 @@ fn scan_right(
                 }
                 let right_fixup =
                     fixup.rightmost_subexpression_fixup(true, false, Precedence::Jump);
-                match scan_right(e, right_fixup, Precedence::Jump, 1, 1) {
+                match scan_right(e, right_fixup, Precedence::Jump, 1, 1, depth + 1) {
                     Scan::Fail => Scan::Bailout,
                     Scan::Bailout | Scan::Consume => Scan::Consume,
                 }
@@ -610,6 +635,7 @@ fn scan_right(
                 } else {
                     1
                 },
+                depth + 1,
             );
             match scan {
                 Scan::Fail | Scan::Bailout if fixup.next_operator < Precedence::Let => {
@@ -618,7 +644,9 @@ fn scan_right(
                 Scan::Consume => return Scan::Consume,
                 _ => {}
             }
-            if right_fixup.rightmost_subexpression_precedence(&e.expr) < Precedence::Let {
+            if right_fixup.rightmost_subexpression_precedence_at_depth(&e.expr, depth + 1)
+                < Precedence::Let
+            {
                 Scan::Consume
             } else if let Scan::Fail = scan {
                 Scan::Bailout
@@ -626,7 +654,14 @@ fn scan_right(
                 Scan::Consume
             }
         }
-        Expr::Group(e) => scan_right(&e.expr, fixup, precedence, fail_offset, bailout_offset),
+        Expr::Group(e) => scan_right(
+            &e.expr,
+            fixup,
+            precedence,
+            fail_offset,
+            bailout_offset,
+            depth + 1,
+        ),
         Expr::Array(_)
         | Expr::Async(_)
         | Expr::Await(_)
@@ -0,0 +1,78 @@
+use crate::algorithm::Printer;
+use crate::config::Config;
+use crate::depth::DepthError;
+use crate::file;
+use alloc::string::String;
+use syn::File;
+
+/// Prints any number of files with the same configuration.
+///
+/// The output is the same as from [`unparse_with_config`], but files are
+/// always printed on the current thread. Printing with
+/// [`format_into`][Formatter::format_into] into the same `String` each time
+/// reuses its memory for the next file.
+///
+/// [`unparse_with_config`]: crate::unparse_with_config
+///
+/// ```
+/// # let files: Vec<syn::File> = Vec::new();
+/// let mut formatter = prettyplease::Formatter::new(prettyplease::Config::default());
+/// let mut output = String::new();
+/// for file in &files {
+///     formatter.format_into(file, &mut output);
+///     print!("{}", output);
+/// }
+/// ```
+pub struct Formatter {
+    printer: Printer,
+}
+
+impl Formatter {
+    pub fn new(config: Config) -> Self {
+        Formatter {
+            printer: Printer::with_config(config),
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.printer.config
+    }
+
+    /// # Panics
+    ///
+    /// Panics if expressions are nested deeper than the configured
+    /// `max_depth`. Use [`try_format`][Formatter::try_format] to handle that
+    /// as an error.
+    pub fn format(&mut self, file: &File) -> String {
+        match self.try_format(file) {
+            Ok(output) => output,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    pub fn try_format(&mut self, file: &File) -> Result<String, DepthError> {
+        let mut out = String::new();
+        file::print(&mut self.printer, file, &mut out)?;
+        Ok(out)
+    }
+
+    /// Like [`format`][Formatter::format], but writes the output into `out`,
+    /// replacing what was there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if expressions are nested deeper than the configured
+    /// `max_depth`. Use [`try_format_into`][Formatter::try_format_into] to
+    /// handle that as an error.
+    pub fn format_into(&mut self, file: &File, out: &mut String) {
+        if let Err(error) = self.try_format_into(file, out) {
+            panic!("{}", error);
+        }
+    }
+
+    /// Like [`try_format`][Formatter::try_format], but writes the output into
+    /// `out`, replacing what was there. On error `out` is left unchanged.
+    pub fn try_format_into(&mut self, file: &File, out: &mut String) -> Result<(), DepthError> {
+        file::print(&mut self.printer, file, out)
+    }
+}
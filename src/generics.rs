@@ -1,9 +1,11 @@
-use crate::algorithm::Printer;
+use crate::algorithm::{self, BreakToken, Printer};
+use crate::config::{BraceStyle, TrailingComma, WhereStyle};
 use crate::iter::IterDelimited;
 use crate::path::PathKind;
 use crate::INDENT;
+use alloc::format;
+use core::ptr;
 use proc_macro2::TokenStream;
-use std::ptr;
 use syn::{
     BoundLifetimes, CapturedParam, ConstParam, Expr, GenericParam, Generics, LifetimeParam,
     PreciseCapture, PredicateLifetime, PredicateType, TraitBound, TraitBoundModifier, TypeParam,
@@ -83,7 +85,7 @@ impl Printer {
     fn type_param(&mut self, type_param: &TypeParam) {
         self.outer_attrs(&type_param.attrs);
         self.ident(&type_param.ident);
-        self.ibox(INDENT);
+        self.bounds_box();
         for type_param_bound in type_param.bounds.iter().delimited() {
             if type_param_bound.is_first {
                 self.word(": ");
@@ -148,7 +150,7 @@ impl Printer {
 
     #[cfg(not(feature = "verbatim"))]
     fn type_param_bound_verbatim(&mut self, bound: &TokenStream) {
-        unimplemented!("TypeParamBound::Verbatim `{}`", bound);
+        self.unsupported(format!("TypeParamBound::Verbatim `{}`", bound));
     }
 
     #[cfg(feature = "verbatim")]
@@ -187,7 +189,10 @@ impl Printer {
 
         let bound: TypeParamBoundVerbatim = match syn::parse2(tokens.clone()) {
             Ok(bound) => bound,
-            Err(_) => unimplemented!("TypeParamBound::Verbatim `{}`", tokens),
+            Err(_) => {
+                self.unsupported(format!("TypeParamBound::Verbatim `{}`", tokens));
+                return;
+            }
         };
 
         match bound {
@@ -214,11 +219,26 @@ impl Printer {
     }
 
     pub fn where_clause_for_body(&mut self, where_clause: &Option<WhereClause>) {
+        match where_clause {
+            Some(where_clause) if !where_clause.predicates.is_empty() => {}
+            _ => return self.space_before_body(),
+        }
         let hardbreaks = true;
         let semi = false;
         self.where_clause_impl(where_clause, hardbreaks, semi);
     }
 
+    // Between an item without a where-clause and the opening brace of its
+    // body.
+    pub fn space_before_body(&mut self) {
+        if self.config.brace_style == BraceStyle::AlwaysNextLine {
+            self.hardbreak();
+            self.offset(-INDENT);
+        } else {
+            self.nbsp();
+        }
+    }
+
     pub fn where_clause_semi(&mut self, where_clause: &Option<WhereClause>) {
         let hardbreaks = true;
         let semi = true;
@@ -255,22 +275,43 @@ impl Printer {
             }
         };
         if hardbreaks {
-            self.hardbreak();
-            self.offset(-INDENT);
-            self.word("where");
-            self.hardbreak();
+            let legacy = self.config.where_style == WhereStyle::Legacy;
+            self.where_break(None, "");
+            if legacy {
+                self.word("where ");
+            } else {
+                self.offset(-INDENT);
+                self.word("where");
+                self.where_break(None, "");
+            }
             for predicate in where_clause.predicates.iter().delimited() {
                 self.where_predicate(&predicate);
-                if predicate.is_last && semi {
+                if !predicate.is_last {
+                    self.word(",");
+                    // Legacy style aligns the predicates after `where `.
+                    self.where_break(None, if legacy { "      " } else { "" });
+                } else if semi {
                     self.word(";");
                 } else {
-                    self.word(",");
-                    self.hardbreak();
+                    let pre_break = match self.config.trailing_comma {
+                        TrailingComma::Vertical if !legacy => Some(','),
+                        TrailingComma::Vertical | TrailingComma::Never => None,
+                        TrailingComma::Always => {
+                            self.word(",");
+                            None
+                        }
+                    };
+                    if self.config.brace_style == BraceStyle::PreferSameLine {
+                        if pre_break.is_some() && !self.config.where_same_line {
+                            self.word(",");
+                        }
+                        self.nbsp();
+                    } else {
+                        self.where_break(pre_break, "");
+                        self.offset(-INDENT);
+                    }
                 }
             }
-            if !semi {
-                self.offset(-INDENT);
-            }
         } else {
             self.space();
             self.offset(-INDENT);
@@ -290,6 +331,21 @@ impl Printer {
         }
     }
 
+    // A line break within a where-clause that is laid out on lines of its
+    // own, unless it fits on the line of the signature.
+    fn where_break(&mut self, pre_break: Option<char>, post_break: &'static str) {
+        self.scan_break(BreakToken {
+            blank_space: if self.config.where_same_line {
+                1
+            } else {
+                algorithm::SIZE_INFINITY as usize
+            },
+            pre_break,
+            post_break,
+            ..BreakToken::default()
+        });
+    }
+
     fn where_predicate(&mut self, predicate: &WherePredicate) {
         match predicate {
             #![cfg_attr(all(test, exhaustive), deny(non_exhaustive_omitted_patterns))]
@@ -308,7 +364,7 @@ impl Printer {
         if predicate.bounds.len() == 1 {
             self.ibox(0);
         } else {
-            self.ibox(INDENT);
+            self.bounds_box();
         }
         for type_param_bound in predicate.bounds.iter().delimited() {
             if type_param_bound.is_first {
@@ -322,6 +378,14 @@ impl Printer {
         self.end();
     }
 
+    pub fn bounds_box(&mut self) {
+        if self.config.vertical_bounds {
+            self.cbox(INDENT);
+        } else {
+            self.ibox(INDENT);
+        }
+    }
+
     fn predicate_lifetime(&mut self, predicate: &PredicateLifetime) {
         self.lifetime(&predicate.lifetime);
         self.word(":");
@@ -0,0 +1,109 @@
+use alloc::collections::BTreeMap;
+#[cfg(feature = "hygiene")]
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "hygiene")]
+use alloc::string::ToString;
+use proc_macro2::Span;
+#[cfg(feature = "hygiene")]
+use proc_macro2::{Ident, TokenStream, TokenTree};
+#[cfg(feature = "hygiene")]
+use syn::visit::{self, Visit};
+#[cfg(feature = "hygiene")]
+use syn::{Item, Macro, PatIdent};
+
+// Identifiers to print under a different name, keyed by name and hygiene
+// context.
+pub(crate) type Renames = BTreeMap<(String, u32), String>;
+
+// Within one top-level item, find the local bindings that are spelled the
+// same but come from different hygiene contexts. The binding from the
+// earliest context keeps its name and the others get a numeric suffix.
+#[cfg(feature = "hygiene")]
+pub(crate) fn renames(item: &Item) -> Renames {
+    let mut collect = Collect {
+        names: BTreeSet::new(),
+        bindings: BTreeMap::new(),
+    };
+    collect.visit_item(item);
+
+    let mut renames = Renames::new();
+    for (name, contexts) in collect.bindings {
+        let base = name.strip_prefix("r#").unwrap_or(&name);
+        let mut suffix = 1;
+        for context in contexts.into_iter().skip(1) {
+            let renamed = loop {
+                let candidate = format!("{}_{}", base, suffix);
+                suffix += 1;
+                if !collect.names.contains(&candidate) {
+                    break candidate;
+                }
+            };
+            collect.names.insert(renamed.clone());
+            renames.insert((name.clone(), context), renamed);
+        }
+    }
+    renames
+}
+
+// The compiler's spans print their syntax context as a `#N` prefix, as in
+// `#3 bytes(10..20)`. This is not a stable format; tests/test_hygiene.rs
+// checks that it still holds. Spans from outside of a procedural macro do not
+// carry hygiene information and all belong to context 0.
+pub(crate) fn context(span: Span) -> u32 {
+    let debug = format!("{:?}", span);
+    debug
+        .strip_prefix('#')
+        .and_then(|rest| rest.split(' ').next())
+        .and_then(|context| context.parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(feature = "hygiene")]
+struct Collect {
+    // Every identifier in the item, so that a renamed binding does not
+    // collide with an unrelated identifier of the same spelling.
+    names: BTreeSet<String>,
+    // Hygiene contexts in which each name is bound by a pattern. Uses of the
+    // name refer to the binding from the same context, so those are renamed
+    // along with it by Printer::ident.
+    bindings: BTreeMap<String, BTreeSet<u32>>,
+}
+
+#[cfg(feature = "hygiene")]
+impl Collect {
+    fn binding(&mut self, ident: &Ident) {
+        self.bindings
+            .entry(ident.to_string())
+            .or_default()
+            .insert(context(ident.span()));
+    }
+
+    fn tokens(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) => self.tokens(group.stream()),
+                TokenTree::Ident(ident) => self.visit_ident(&ident),
+                TokenTree::Punct(_) | TokenTree::Literal(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(feature = "hygiene")]
+impl<'ast> Visit<'ast> for Collect {
+    fn visit_ident(&mut self, ident: &'ast Ident) {
+        self.names.insert(ident.to_string());
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        self.tokens(mac.tokens.clone());
+        visit::visit_macro(self, mac);
+    }
+
+    fn visit_pat_ident(&mut self, pat: &'ast PatIdent) {
+        self.binding(&pat.ident);
+        visit::visit_pat_ident(self, pat);
+    }
+}
@@ -1,9 +1,12 @@
 use crate::algorithm::Printer;
+use crate::config::Edition;
 use crate::fixup::FixupContext;
 use crate::iter::IterDelimited;
 use crate::mac;
 use crate::path::PathKind;
 use crate::INDENT;
+use alloc::format;
+use core::mem;
 use proc_macro2::TokenStream;
 use syn::{
     Fields, FnArg, ForeignItem, ForeignItemFn, ForeignItemMacro, ForeignItemStatic,
@@ -58,16 +61,18 @@ impl Printer {
     fn item_enum(&mut self, item: &ItemEnum) {
         self.outer_attrs(&item.attrs);
         self.cbox(INDENT);
+        self.cbox(0);
         self.visibility(&item.vis);
         self.word("enum ");
         self.ident(&item.ident);
         self.generics(&item.generics);
         self.where_clause_for_body(&item.generics.where_clause);
+        self.end();
         self.word("{");
         self.hardbreak_if_nonempty();
-        for variant in &item.variants {
-            self.variant(variant);
-            self.word(",");
+        for variant in item.variants.iter().delimited() {
+            self.variant(&variant);
+            self.vertical_comma(variant.is_last);
             self.hardbreak();
         }
         self.offset(-INDENT);
@@ -92,6 +97,7 @@ impl Printer {
     fn item_fn(&mut self, item: &ItemFn) {
         self.outer_attrs(&item.attrs);
         self.cbox(INDENT);
+        self.cbox(0);
         self.visibility(&item.vis);
         self.signature(
             &item.sig,
@@ -99,6 +105,7 @@ impl Printer {
             &verbatim::Safety::Disallowed,
         );
         self.where_clause_for_body(&item.sig.generics.where_clause);
+        self.end();
         self.word("{");
         self.hardbreak_if_nonempty();
         self.inner_attrs(&item.attrs);
@@ -114,7 +121,7 @@ impl Printer {
     fn item_foreign_mod(&mut self, item: &ItemForeignMod) {
         self.outer_attrs(&item.attrs);
         self.cbox(INDENT);
-        if item.unsafety.is_some() {
+        if item.unsafety.is_some() || self.config.edition >= Some(Edition::E2024) {
             self.word("unsafe ");
         }
         self.abi(&item.abi);
@@ -133,6 +140,7 @@ impl Printer {
     fn item_impl(&mut self, item: &ItemImpl) {
         self.outer_attrs(&item.attrs);
         self.cbox(INDENT);
+        self.cbox(0);
         self.ibox(-INDENT);
         self.cbox(INDENT);
         if item.defaultness.is_some() {
@@ -156,6 +164,7 @@ impl Printer {
         self.ty(&item.self_ty);
         self.end();
         self.where_clause_for_body(&item.generics.where_clause);
+        self.end();
         self.word("{");
         self.hardbreak_if_nonempty();
         self.inner_attrs(&item.attrs);
@@ -185,12 +194,17 @@ impl Printer {
         self.word("mod ");
         self.ident(&item.ident);
         if let Some((_brace, items)) = &item.content {
-            self.word(" {");
+            self.space_before_body();
+            self.word("{");
             self.hardbreak_if_nonempty();
+            let nested = self.shortening.nested(item, &self.config);
+            let outer = mem::replace(&mut self.shortening, nested);
             self.inner_attrs(&item.attrs);
+            self.shortened_imports();
             for item in items {
                 self.item(item);
             }
+            self.shortening = outer;
             self.offset(-INDENT);
             self.end();
             self.word("}");
@@ -221,6 +235,7 @@ impl Printer {
     fn item_struct(&mut self, item: &ItemStruct) {
         self.outer_attrs(&item.attrs);
         self.cbox(INDENT);
+        self.cbox(0);
         self.visibility(&item.vis);
         self.word("struct ");
         self.ident(&item.ident);
@@ -228,11 +243,12 @@ impl Printer {
         match &item.fields {
             Fields::Named(fields) => {
                 self.where_clause_for_body(&item.generics.where_clause);
+                self.end();
                 self.word("{");
                 self.hardbreak_if_nonempty();
-                for field in &fields.named {
-                    self.field(field);
-                    self.word(",");
+                for field in fields.named.iter().delimited() {
+                    self.field(&field);
+                    self.vertical_comma(field.is_last);
                     self.hardbreak();
                 }
                 self.offset(-INDENT);
@@ -243,10 +259,12 @@ impl Printer {
                 self.fields_unnamed(fields);
                 self.where_clause_semi(&item.generics.where_clause);
                 self.end();
+                self.end();
             }
             Fields::Unit => {
                 self.where_clause_semi(&item.generics.where_clause);
                 self.end();
+                self.end();
             }
         }
         self.hardbreak();
@@ -255,6 +273,7 @@ impl Printer {
     fn item_trait(&mut self, item: &ItemTrait) {
         self.outer_attrs(&item.attrs);
         self.cbox(INDENT);
+        self.cbox(0);
         self.visibility(&item.vis);
         if item.unsafety.is_some() {
             self.word("unsafe ");
@@ -274,6 +293,7 @@ impl Printer {
             self.type_param_bound(&supertrait);
         }
         self.where_clause_for_body(&item.generics.where_clause);
+        self.end();
         self.word("{");
         self.hardbreak_if_nonempty();
         self.inner_attrs(&item.attrs);
@@ -289,6 +309,7 @@ impl Printer {
     fn item_trait_alias(&mut self, item: &ItemTraitAlias) {
         self.outer_attrs(&item.attrs);
         self.cbox(INDENT);
+        self.cbox(0);
         self.visibility(&item.vis);
         self.word("trait ");
         self.ident(&item.ident);
@@ -304,6 +325,7 @@ impl Printer {
         }
         self.where_clause_semi(&item.generics.where_clause);
         self.end();
+        self.end();
         self.hardbreak();
     }
 
@@ -328,16 +350,18 @@ impl Printer {
     fn item_union(&mut self, item: &ItemUnion) {
         self.outer_attrs(&item.attrs);
         self.cbox(INDENT);
+        self.cbox(0);
         self.visibility(&item.vis);
         self.word("union ");
         self.ident(&item.ident);
         self.generics(&item.generics);
         self.where_clause_for_body(&item.generics.where_clause);
+        self.end();
         self.word("{");
         self.hardbreak_if_nonempty();
-        for field in &item.fields.named {
-            self.field(field);
-            self.word(",");
+        for field in item.fields.named.iter().delimited() {
+            self.field(&field);
+            self.vertical_comma(field.is_last);
             self.hardbreak();
         }
         self.offset(-INDENT);
@@ -358,16 +382,26 @@ impl Printer {
         self.hardbreak();
     }
 
+    pub(crate) fn shortened_imports(&mut self) {
+        for import in mem::take(&mut self.shortening.imports) {
+            self.word("use ");
+            self.word(import);
+            self.word(";");
+            self.hardbreak();
+        }
+    }
+
     #[cfg(not(feature = "verbatim"))]
     fn item_verbatim(&mut self, item: &TokenStream) {
         if !item.is_empty() {
-            unimplemented!("Item::Verbatim `{}`", item);
+            self.unsupported(format!("Item::Verbatim `{}`", item));
         }
         self.hardbreak();
     }
 
     #[cfg(feature = "verbatim")]
     fn item_verbatim(&mut self, tokens: &TokenStream) {
+        use alloc::vec::Vec;
         use syn::parse::{Parse, ParseStream, Result};
         use syn::punctuated::Punctuated;
         use syn::{
@@ -575,7 +609,10 @@ impl Printer {
 
         let item: ItemVerbatim = match syn::parse2(tokens.clone()) {
             Ok(item) => item,
-            Err(_) => unimplemented!("Item::Verbatim `{}`", tokens),
+            Err(_) => {
+                self.unsupported(format!("Item::Verbatim `{}`", tokens));
+                return;
+            }
         };
 
         match item {
@@ -595,6 +632,7 @@ impl Printer {
             ItemVerbatim::ImplFlexible(item) => {
                 self.outer_attrs(&item.attrs);
                 self.cbox(INDENT);
+                self.cbox(0);
                 self.ibox(-INDENT);
                 self.cbox(INDENT);
                 self.visibility(&item.vis);
@@ -624,6 +662,7 @@ impl Printer {
                 self.ty(&item.self_ty);
                 self.end();
                 self.where_clause_for_body(&item.generics.where_clause);
+                self.end();
                 self.word("{");
                 self.hardbreak_if_nonempty();
                 self.inner_attrs(&item.attrs);
@@ -784,7 +823,7 @@ impl Printer {
         }
     }
 
-    fn foreign_item(&mut self, foreign_item: &ForeignItem) {
+    pub(crate) fn foreign_item(&mut self, foreign_item: &ForeignItem) {
         match foreign_item {
             #![cfg_attr(all(test, exhaustive), deny(non_exhaustive_omitted_patterns))]
             ForeignItem::Fn(item) => self.foreign_item_fn(item),
@@ -799,6 +838,7 @@ impl Printer {
     fn foreign_item_fn(&mut self, foreign_item: &ForeignItemFn) {
         self.outer_attrs(&foreign_item.attrs);
         self.cbox(INDENT);
+        self.cbox(0);
         self.visibility(&foreign_item.vis);
         self.signature(
             &foreign_item.sig,
@@ -807,6 +847,7 @@ impl Printer {
         );
         self.where_clause_semi(&foreign_item.sig.generics.where_clause);
         self.end();
+        self.end();
         self.hardbreak();
     }
 
@@ -846,7 +887,7 @@ impl Printer {
     #[cfg(not(feature = "verbatim"))]
     fn foreign_item_verbatim(&mut self, foreign_item: &TokenStream) {
         if !foreign_item.is_empty() {
-            unimplemented!("ForeignItem::Verbatim `{}`", foreign_item);
+            self.unsupported(format!("ForeignItem::Verbatim `{}`", foreign_item));
         }
         self.hardbreak();
     }
@@ -917,7 +958,10 @@ impl Printer {
 
         let foreign_item: ForeignItemVerbatim = match syn::parse2(tokens.clone()) {
             Ok(foreign_item) => foreign_item,
-            Err(_) => unimplemented!("ForeignItem::Verbatim `{}`", tokens),
+            Err(_) => {
+                self.unsupported(format!("ForeignItem::Verbatim `{}`", tokens));
+                return;
+            }
         };
 
         match foreign_item {
@@ -940,7 +984,7 @@ impl Printer {
         }
     }
 
-    fn trait_item(&mut self, trait_item: &TraitItem) {
+    pub(crate) fn trait_item(&mut self, trait_item: &TraitItem) {
         match trait_item {
             #![cfg_attr(all(test, exhaustive), deny(non_exhaustive_omitted_patterns))]
             TraitItem::Const(item) => self.trait_item_const(item),
@@ -973,6 +1017,7 @@ impl Printer {
     fn trait_item_fn(&mut self, trait_item: &TraitItemFn) {
         self.outer_attrs(&trait_item.attrs);
         self.cbox(INDENT);
+        self.cbox(0);
         self.signature(
             &trait_item.sig,
             #[cfg(feature = "verbatim")]
@@ -980,6 +1025,7 @@ impl Printer {
         );
         if let Some(block) = &trait_item.default {
             self.where_clause_for_body(&trait_item.sig.generics.where_clause);
+            self.end();
             self.word("{");
             self.hardbreak_if_nonempty();
             self.inner_attrs(&trait_item.attrs);
@@ -992,6 +1038,7 @@ impl Printer {
         } else {
             self.where_clause_semi(&trait_item.sig.generics.where_clause);
             self.end();
+            self.end();
         }
         self.hardbreak();
     }
@@ -1033,13 +1080,14 @@ impl Printer {
     #[cfg(not(feature = "verbatim"))]
     fn trait_item_verbatim(&mut self, trait_item: &TokenStream) {
         if !trait_item.is_empty() {
-            unimplemented!("TraitItem::Verbatim `{}`", trait_item);
+            self.unsupported(format!("TraitItem::Verbatim `{}`", trait_item));
         }
         self.hardbreak();
     }
 
     #[cfg(feature = "verbatim")]
     fn trait_item_verbatim(&mut self, tokens: &TokenStream) {
+        use alloc::vec::Vec;
         use syn::parse::{Parse, ParseStream, Result};
         use syn::{Attribute, Ident, Token, Visibility};
         use verbatim::{FlexibleItemConst, FlexibleItemType, WhereClauseLocation};
@@ -1106,7 +1154,10 @@ impl Printer {
 
         let impl_item: TraitItemVerbatim = match syn::parse2(tokens.clone()) {
             Ok(impl_item) => impl_item,
-            Err(_) => unimplemented!("TraitItem::Verbatim `{}`", tokens),
+            Err(_) => {
+                self.unsupported(format!("TraitItem::Verbatim `{}`", tokens));
+                return;
+            }
         };
 
         match impl_item {
@@ -1134,7 +1185,7 @@ impl Printer {
         }
     }
 
-    fn impl_item(&mut self, impl_item: &ImplItem) {
+    pub(crate) fn impl_item(&mut self, impl_item: &ImplItem) {
         match impl_item {
             #![cfg_attr(all(test, exhaustive), deny(non_exhaustive_omitted_patterns))]
             ImplItem::Const(item) => self.impl_item_const(item),
@@ -1169,6 +1220,7 @@ impl Printer {
     fn impl_item_fn(&mut self, impl_item: &ImplItemFn) {
         self.outer_attrs(&impl_item.attrs);
         self.cbox(INDENT);
+        self.cbox(0);
         self.visibility(&impl_item.vis);
         if impl_item.defaultness.is_some() {
             self.word("default ");
@@ -1179,6 +1231,7 @@ impl Printer {
             &verbatim::Safety::Disallowed,
         );
         self.where_clause_for_body(&impl_item.sig.generics.where_clause);
+        self.end();
         self.word("{");
         self.hardbreak_if_nonempty();
         self.inner_attrs(&impl_item.attrs);
@@ -1221,7 +1274,7 @@ impl Printer {
     #[cfg(not(feature = "verbatim"))]
     fn impl_item_verbatim(&mut self, impl_item: &TokenStream) {
         if !impl_item.is_empty() {
-            unimplemented!("ImplItem::Verbatim `{}`", impl_item);
+            self.unsupported(format!("ImplItem::Verbatim `{}`", impl_item));
         }
         self.hardbreak();
     }
@@ -1282,7 +1335,10 @@ impl Printer {
 
         let impl_item: ImplItemVerbatim = match syn::parse2(tokens.clone()) {
             Ok(impl_item) => impl_item,
-            Err(_) => unimplemented!("ImplItem::Verbatim `{}`", tokens),
+            Err(_) => {
+                self.unsupported(format!("ImplItem::Verbatim `{}`", tokens));
+                return;
+            }
         };
 
         match impl_item {
@@ -1426,6 +1482,7 @@ mod verbatim {
     use crate::fixup::FixupContext;
     use crate::iter::IterDelimited;
     use crate::INDENT;
+    use alloc::vec::Vec;
     use syn::ext::IdentExt;
     use syn::parse::{Parse, ParseStream, Result};
     use syn::{
@@ -1721,6 +1778,7 @@ mod verbatim {
         pub fn flexible_item_fn(&mut self, item: &FlexibleItemFn) {
             self.outer_attrs(&item.attrs);
             self.cbox(INDENT);
+            self.cbox(0);
             self.visibility(&item.vis);
             if item.defaultness {
                 self.word("default ");
@@ -1728,6 +1786,7 @@ mod verbatim {
             self.signature(&item.sig, &item.safety);
             if let Some(body) = &item.body {
                 self.where_clause_for_body(&item.sig.generics.where_clause);
+                self.end();
                 self.word("{");
                 self.hardbreak_if_nonempty();
                 self.inner_attrs(&item.attrs);
@@ -1740,6 +1799,7 @@ mod verbatim {
             } else {
                 self.where_clause_semi(&item.sig.generics.where_clause);
                 self.end();
+                self.end();
             }
             self.hardbreak();
         }
@@ -1,5 +1,5 @@
-use std::iter::Peekable;
-use std::ops::Deref;
+use core::iter::Peekable;
+use core::ops::Deref;
 
 pub struct Delimited<I: Iterator> {
     is_first: bool,
@@ -321,6 +321,7 @@
 //! deferred and populated after it's known that the group is or is not broken.
 
 #![doc(html_root_url = "https://docs.rs/prettyplease/0.2.29")]
+#![no_std]
 #![allow(
     clippy::bool_to_int_with_if,
     clippy::cast_possible_wrap,
@@ -337,6 +338,7 @@
     clippy::needless_pass_by_value,
     clippy::ref_option,
     clippy::similar_names,
+    clippy::single_match_else,
     clippy::struct_excessive_bools,
     clippy::too_many_lines,
     clippy::unused_self,
@@ -344,15 +346,23 @@
 )]
 #![cfg_attr(all(test, exhaustive), feature(non_exhaustive_omitted_patterns_lint))]
 
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 pub mod algorithm;
 mod attr;
 mod classify;
+mod config;
 mod convenience;
 mod data;
+mod depth;
 pub mod expr;
 mod file;
 pub mod fixup;
+mod formatter;
 mod generics;
+mod hygiene;
 mod item;
 pub mod iter;
 mod lifetime;
@@ -362,15 +372,29 @@ mod pat;
 mod path;
 mod precedence;
 mod ring;
+mod shorten;
 pub mod stmt;
 mod token;
 mod ty;
+mod validate;
+#[cfg(feature = "verify")]
+mod verify;
 
-use crate::algorithm::Printer;
+use alloc::string::String;
+use alloc::vec::Vec;
 use syn::File;
 
-// Target line width.
-const MARGIN: isize = 89;
+pub use crate::config::{
+    BraceStyle, Config, ControlBraceStyle, Edition, TrailingComma, WhereStyle,
+};
+pub use crate::depth::DepthError;
+pub use crate::formatter::Formatter;
+pub use crate::validate::{validate, Diagnostic, DiagnosticKind};
+#[cfg(feature = "verify")]
+pub use crate::verify::{
+    check_idempotent, check_idempotent_with_config, unparse_checked, IdempotencyError,
+    RoundTripError,
+};
 
 // Number of spaces increment at each level of block indentation.
 pub const INDENT: isize = 4;
@@ -379,7 +403,37 @@ pub const INDENT: isize = 4;
 const MIN_SPACE: isize = 60;
 
 pub fn unparse(file: &File) -> String {
-    let mut p = Printer::new();
-    p.file(file);
-    p.eof()
+    unparse_with_config(file, &Config::default())
+}
+
+/// # Panics
+///
+/// Panics if expressions are nested deeper than `config.max_depth`. Use
+/// [`try_unparse_with_config`] to handle that as an error.
+pub fn unparse_with_config(file: &File, config: &Config) -> String {
+    match file::unparse(file, config) {
+        Ok(output) => output,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+/// Like [`unparse_with_config`], but returns an error instead of panicking if
+/// expressions are nested deeper than `config.max_depth`.
+pub fn try_unparse_with_config(file: &File, config: &Config) -> Result<String, DepthError> {
+    file::unparse(file, config)
+}
+
+/// Prints a batch of files with the same configuration, one after another on
+/// the current thread, using a [`Formatter`].
+///
+/// # Panics
+///
+/// Panics if expressions are nested deeper than `config.max_depth` in any of
+/// the files.
+pub fn unparse_many<'a>(files: impl IntoIterator<Item = &'a File>, config: &Config) -> Vec<String> {
+    let mut formatter = Formatter::new(config.clone());
+    files
+        .into_iter()
+        .map(|file| formatter.format(file))
+        .collect()
 }
@@ -4,6 +4,6 @@ use syn::Lifetime;
 impl Printer {
     pub fn lifetime(&mut self, lifetime: &Lifetime) {
         self.word("'");
-        self.ident(&lifetime.ident);
+        self.word_display(&lifetime.ident);
     }
 }
@@ -1,4 +1,5 @@
-use crate::algorithm::Printer;
+use crate::algorithm::{Printer, Text};
+use core::fmt::Write as _;
 use proc_macro2::Literal;
 use syn::{Lit, LitBool, LitByte, LitByteStr, LitCStr, LitChar, LitFloat, LitInt, LitStr};
 
@@ -20,31 +21,40 @@ impl Printer {
     }
 
     pub fn lit_str(&mut self, lit: &LitStr) {
-        self.word(lit.token().to_string());
+        self.word_display(&lit.token());
     }
 
     fn lit_byte_str(&mut self, lit: &LitByteStr) {
-        self.word(lit.token().to_string());
+        self.word_display(&lit.token());
     }
 
     fn lit_c_str(&mut self, lit: &LitCStr) {
-        self.word(lit.token().to_string());
+        self.word_display(&lit.token());
     }
 
     fn lit_byte(&mut self, lit: &LitByte) {
-        self.word(lit.token().to_string());
+        self.word_display(&lit.token());
     }
 
     fn lit_char(&mut self, lit: &LitChar) {
-        self.word(lit.token().to_string());
+        self.word_display(&lit.token());
     }
 
     fn lit_int(&mut self, lit: &LitInt) {
-        self.word(lit.token().to_string());
+        self.word_display(&lit.token());
+    }
+
+    // Prints an integer literal in hexadecimal with at least the given number
+    // of digits, keeping its suffix.
+    pub fn lit_int_hex(&mut self, lit: &LitInt, digits: usize) {
+        let value: u128 = lit.base10_parse().unwrap();
+        let mut text = Text::new();
+        write!(text, "0x{:02$x}{}", value, lit.suffix(), digits).unwrap();
+        self.word(text);
     }
 
     fn lit_float(&mut self, lit: &LitFloat) {
-        self.word(lit.token().to_string());
+        self.word_display(&lit.token());
     }
 
     fn lit_bool(&mut self, lit: &LitBool) {
@@ -52,6 +62,6 @@ impl Printer {
     }
 
     fn lit_verbatim(&mut self, token: &Literal) {
-        self.word(token.to_string());
+        self.word_display(token);
     }
 }
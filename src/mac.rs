@@ -1,7 +1,10 @@
-use crate::algorithm::Printer;
+use crate::algorithm::{Printer, Text};
+use crate::config::Edition;
 use crate::path::PathKind;
 use crate::token::Token;
 use crate::INDENT;
+use alloc::borrow::ToOwned;
+use alloc::string::ToString;
 use proc_macro2::{Delimiter, Spacing, TokenStream};
 use syn::{Ident, Macro, MacroDelimiter};
 
@@ -109,7 +112,10 @@ impl Printer {
                     self.hardbreak();
                     state = Start;
                 }
-                _ => unimplemented!("bad macro_rules syntax"),
+                _ => {
+                    self.unsupported("bad macro_rules syntax".to_owned());
+                    return;
+                }
             }
         }
         match state {
@@ -150,7 +156,10 @@ impl Printer {
         let mut state = Start;
         let mut previous_is_joint = true;
         for tt in stream {
-            let token = Token::from(tt);
+            let mut token = Token::from(tt);
+            if let (DollarIdentColon, Token::Ident(fragment)) = (&state, &mut token) {
+                self.fragment_specifier(fragment);
+            }
             let (needs_space, next_state) = match (&state, &token) {
                 (Dollar, Token::Ident(_)) => (false, if matcher { DollarIdent } else { Other }),
                 (DollarIdent, Token::Punct(':', Spacing::Alone)) => (false, DollarIdentColon),
@@ -173,10 +182,12 @@ impl Printer {
                 (Colon, Token::Punct(':', _)) => (false, Colon2),
                 (_, Token::Group(Delimiter::Parenthesis | Delimiter::Bracket, _)) => (true, Delim),
                 (_, Token::Group(Delimiter::Brace | Delimiter::None, _)) => (true, Other),
-                (_, Token::Ident(ident)) if !is_keyword(ident) => {
+                (_, Token::Ident(ident)) if !is_keyword(&Text::display(&ident)) => {
                     (state != Dot && state != Colon2, Ident)
                 }
-                (_, Token::Literal(lit)) if lit.to_string().ends_with('.') => (state != Dot, Other),
+                (_, Token::Literal(lit)) if Text::display(&lit).ends_with('.') => {
+                    (state != Dot, Other)
+                }
                 (_, Token::Literal(_)) => (state != Dot, Ident),
                 (_, Token::Punct(',' | ';', _)) => (false, Other),
                 (_, Token::Punct('.', _)) if !matcher => (state != Ident && state != Delim, Dot),
@@ -207,6 +218,25 @@ impl Printer {
             state = next_state;
         }
     }
+
+    // Fragment specifiers that are spelled differently in older editions
+    // while matching the same syntax.
+    fn fragment_specifier(&self, fragment: &mut Ident) {
+        let replacement = match fragment.to_string().as_str() {
+            "expr_2021" if is_before(self.config.edition, Edition::E2024) => "expr",
+            "pat_param" if is_before(self.config.edition, Edition::E2021) => "pat",
+            _ => return,
+        };
+        *fragment = Ident::new(replacement, fragment.span());
+    }
+}
+
+// Whether code is known to target an edition older than the given one.
+fn is_before(edition: Option<Edition>, other: Edition) -> bool {
+    match edition {
+        Some(edition) => edition < other,
+        None => false,
+    }
 }
 
 pub(crate) fn requires_semi(delimiter: &MacroDelimiter) -> bool {
@@ -216,8 +246,8 @@ pub(crate) fn requires_semi(delimiter: &MacroDelimiter) -> bool {
     }
 }
 
-fn is_keyword(ident: &Ident) -> bool {
-    match ident.to_string().as_str() {
+fn is_keyword(ident: &str) -> bool {
+    match ident {
         "as" | "async" | "await" | "box" | "break" | "const" | "continue" | "crate" | "dyn"
         | "else" | "enum" | "extern" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop"
         | "macro" | "match" | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "static"
@@ -226,6 +256,21 @@ fn is_keyword(ident: &Ident) -> bool {
     }
 }
 
+// Whether the identifier can only be written as a raw identifier, because it
+// is a strict or reserved keyword in the given edition. The path keywords are
+// excluded; those are never meant as an ordinary identifier, and cannot be
+// raw identifiers anyway.
+pub(crate) fn requires_raw(ident: &str, edition: Option<Edition>) -> bool {
+    match ident {
+        "_" | "crate" | "self" | "Self" | "super" => false,
+        "async" | "await" | "dyn" | "try" => edition >= Some(Edition::E2018),
+        "gen" => edition >= Some(Edition::E2024),
+        "abstract" | "become" | "do" | "false" | "final" | "override" | "priv" | "true"
+        | "typeof" | "unsized" | "virtual" => true,
+        _ => is_keyword(ident),
+    }
+}
+
 #[cfg(feature = "verbatim")]
 mod standard_library {
     use crate::algorithm::Printer;
@@ -233,6 +278,10 @@ mod standard_library {
     use crate::iter::IterDelimited;
     use crate::path::PathKind;
     use crate::INDENT;
+    use alloc::boxed::Box;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
     use syn::ext::IdentExt;
     use syn::parse::{Parse, ParseStream, Parser, Result};
     use syn::{
@@ -244,10 +293,10 @@ mod standard_library {
         Expr(Expr),
         Exprs(Vec<Expr>),
         Cfg(Cfg),
-        Matches(Matches),
+        Matches(Box<Matches>),
         ThreadLocal(Vec<ThreadLocal>),
         VecArray(Vec<Expr>),
-        VecRepeat { elem: Expr, n: Expr },
+        VecRepeat { elem: Box<Expr>, n: Box<Expr> },
     }
 
     enum Cfg {
@@ -428,11 +477,11 @@ mod standard_library {
                 None
             };
             input.parse::<Option<Token![,]>>()?;
-            Ok(KnownMacro::Matches(Matches {
+            Ok(KnownMacro::Matches(Box::new(Matches {
                 expression,
                 pattern,
                 guard,
-            }))
+            })))
         }
 
         fn parse_thread_local(input: ParseStream) -> Result<Self> {
@@ -469,8 +518,8 @@ mod standard_library {
             if input.parse::<Option<Token![;]>>()?.is_some() {
                 let len: Expr = input.parse()?;
                 Ok(KnownMacro::VecRepeat {
-                    elem: first,
-                    n: len,
+                    elem: Box::new(first),
+                    n: Box::new(len),
                 })
             } else {
                 let mut vec = vec![first];
@@ -564,7 +613,7 @@ mod standard_library {
                         self.trailing_comma(elem.is_last);
                     }
                     self.offset(-INDENT);
-                    self.end();
+                    self.end_with_width(self.config.fn_call_width);
                     self.word(")");
                 }
                 KnownMacro::Cfg(cfg) => {
@@ -614,18 +663,7 @@ mod standard_library {
                     self.word("}");
                     semicolon = false;
                 }
-                KnownMacro::VecArray(vec) => {
-                    self.word("[");
-                    self.cbox(INDENT);
-                    self.zerobreak();
-                    for elem in vec.iter().delimited() {
-                        self.expr(&elem, FixupContext::NONE);
-                        self.trailing_comma(elem.is_last);
-                    }
-                    self.offset(-INDENT);
-                    self.end();
-                    self.word("]");
-                }
+                KnownMacro::VecArray(vec) => self.array_elements(vec.iter()),
                 KnownMacro::VecRepeat { elem, n } => {
                     self.word("[");
                     self.cbox(INDENT);
@@ -3,6 +3,7 @@ use crate::fixup::FixupContext;
 use crate::iter::IterDelimited;
 use crate::path::PathKind;
 use crate::INDENT;
+use alloc::format;
 use proc_macro2::TokenStream;
 use syn::{
     FieldPat, Pat, PatIdent, PatOr, PatParen, PatReference, PatRest, PatSlice, PatStruct, PatTuple,
@@ -171,11 +172,12 @@ impl Printer {
 
     #[cfg(not(feature = "verbatim"))]
     fn pat_verbatim(&mut self, pat: &TokenStream) {
-        unimplemented!("Pat::Verbatim `{}`", pat);
+        self.unsupported(format!("Pat::Verbatim `{}`", pat));
     }
 
     #[cfg(feature = "verbatim")]
     fn pat_verbatim(&mut self, tokens: &TokenStream) {
+        use alloc::vec::Vec;
         use syn::parse::{Parse, ParseStream, Result};
         use syn::{braced, Attribute, Block, Token};
 
@@ -218,7 +220,10 @@ impl Printer {
 
         let pat: PatVerbatim = match syn::parse2(tokens.clone()) {
             Ok(pat) => pat,
-            Err(_) => unimplemented!("Pat::Verbatim `{}`", tokens),
+            Err(_) => {
+                self.unsupported(format!("Pat::Verbatim `{}`", tokens));
+                return;
+            }
         };
 
         match pat {
@@ -245,7 +250,7 @@ impl Printer {
 
     fn field_pat(&mut self, field_pat: &FieldPat) {
         self.outer_attrs(&field_pat.attrs);
-        if field_pat.colon_token.is_some() {
+        if field_pat.colon_token.is_some() || self.member_is_renamed(&field_pat.member) {
             self.member(&field_pat.member);
             self.word(": ");
         }
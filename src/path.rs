@@ -1,7 +1,7 @@
 use crate::algorithm::Printer;
 use crate::iter::IterDelimited;
 use crate::INDENT;
-use std::ptr;
+use core::ptr;
 use syn::{
     AngleBracketedGenericArguments, AssocConst, AssocType, Constraint, GenericArgument,
     ParenthesizedGenericArguments, Path, PathArguments, PathSegment, QSelf,
@@ -19,9 +19,14 @@ pub enum PathKind {
 
 impl Printer {
     pub fn path(&mut self, path: &Path, kind: PathKind) {
+        let skip = self.shortening.skip(path);
+        self.path_skip(path, skip, kind);
+    }
+
+    pub fn path_skip(&mut self, path: &Path, skip: usize, kind: PathKind) {
         assert!(!path.segments.is_empty());
-        for segment in path.segments.iter().delimited() {
-            if !segment.is_first || path.leading_colon.is_some() {
+        for segment in path.segments.iter().skip(skip).delimited() {
+            if !segment.is_first || skip == 0 && path.leading_colon.is_some() {
                 self.word("::");
             }
             self.path_segment(&segment, kind);
@@ -33,7 +38,7 @@ impl Printer {
         self.path_arguments(&segment.arguments, kind);
     }
 
-    fn path_arguments(&mut self, arguments: &PathArguments, kind: PathKind) {
+    pub fn path_arguments(&mut self, arguments: &PathArguments, kind: PathKind) {
         match arguments {
             PathArguments::None => {}
             PathArguments::AngleBracketed(arguments) => {
@@ -145,17 +150,17 @@ impl Printer {
     }
 
     fn parenthesized_generic_arguments(&mut self, arguments: &ParenthesizedGenericArguments) {
-        self.cbox(INDENT);
         self.word("(");
+        self.cbox(INDENT);
         self.zerobreak();
         for ty in arguments.inputs.iter().delimited() {
             self.ty(&ty);
             self.trailing_comma(ty.is_last);
         }
         self.offset(-INDENT);
+        self.end();
         self.word(")");
         self.return_type(&arguments.output);
-        self.end();
     }
 
     pub fn qpath(&mut self, qself: &Option<QSelf>, path: &Path, kind: PathKind) {
@@ -1,5 +1,5 @@
-use std::collections::VecDeque;
-use std::ops::{Index, IndexMut, Range};
+use alloc::collections::VecDeque;
+use core::ops::{Index, IndexMut, Range};
 
 pub struct RingBuffer<T> {
     data: VecDeque<T>,
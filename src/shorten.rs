@@ -0,0 +1,344 @@
+use crate::config::{Config, Edition};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use proc_macro2::{TokenStream, TokenTree};
+use syn::visit::{self, Visit};
+use syn::{
+    Attribute, Expr, ForeignItem, Ident, ImplItem, Item, ItemMod, Macro, MetaList, Pat, Path,
+    PathArguments, TraitItem, Type, UseTree,
+};
+
+// How absolute paths are abbreviated within one module.
+#[derive(Clone, Default)]
+pub(crate) struct Shortening {
+    // Leading segments of absolute paths, mapped to the number of those
+    // segments to leave out when printing.
+    prefixes: BTreeMap<Vec<String>, usize>,
+    // Paths to import at the top of the module, as `::a::b::C`.
+    pub imports: Vec<String>,
+    no_std: bool,
+    no_implicit_prelude: bool,
+}
+
+impl Shortening {
+    pub fn root(attrs: &[Attribute], items: &[Item], config: &Config) -> Self {
+        let outer = Shortening {
+            no_std: has_attr(attrs, "no_std"),
+            ..Shortening::default()
+        };
+        outer.module(attrs, items, config)
+    }
+
+    pub fn nested(&self, module: &ItemMod, config: &Config) -> Self {
+        match &module.content {
+            Some((_brace, items)) => self.module(&module.attrs, items, config),
+            None => Shortening::default(),
+        }
+    }
+
+    fn module(&self, attrs: &[Attribute], items: &[Item], config: &Config) -> Self {
+        if !config.shorten_prelude_paths && !config.import_repeated_paths {
+            return Shortening::default();
+        }
+
+        let mut scope = Scope {
+            prelude: BTreeMap::new(),
+            absolute: BTreeMap::new(),
+            relative: BTreeMap::new(),
+            tokens: BTreeMap::new(),
+            other: BTreeMap::new(),
+            glob: false,
+            no_std: self.no_std,
+            edition: config.edition,
+        };
+        for attr in attrs {
+            scope.visit_attribute(attr);
+        }
+        for item in items {
+            scope.visit_item(item);
+        }
+
+        let no_implicit_prelude =
+            self.no_implicit_prelude || has_attr(attrs, "no_implicit_prelude");
+        let mut shortening = Shortening {
+            prefixes: BTreeMap::new(),
+            imports: Vec::new(),
+            no_std: self.no_std,
+            no_implicit_prelude,
+        };
+
+        // A bare name from the prelude means the prelude item as long as
+        // nothing else in the module is declared with that name.
+        let mut bare = BTreeSet::new();
+        if config.shorten_prelude_paths && !no_implicit_prelude && !scope.glob {
+            for (prefix, name) in &scope.prelude {
+                if !scope.other.contains_key(name) {
+                    shortening.prefixes.insert(prefix.clone(), prefix.len() - 1);
+                    bare.insert(name.clone());
+                }
+            }
+        }
+
+        // An imported name must not be used in the module in any other way,
+        // including inside of macro invocations.
+        if config.import_repeated_paths {
+            let mut names = BTreeMap::new();
+            for (path, count) in &scope.absolute {
+                let name = path.last().unwrap();
+                if *count >= 2
+                    && !bare.contains(name)
+                    && !scope.other.contains_key(name)
+                    && !scope.relative.contains_key(name)
+                    && !scope.tokens.contains_key(name)
+                {
+                    names.entry(name).or_insert_with(Vec::new).push(path);
+                }
+            }
+            for paths in names.into_values() {
+                if let [path] = paths[..] {
+                    shortening.prefixes.insert(path.clone(), path.len() - 1);
+                    shortening.imports.push(format!("::{}", path.join("::")));
+                }
+            }
+            shortening.imports.sort();
+        }
+
+        shortening
+    }
+
+    // Number of leading segments of the path to leave out.
+    pub fn skip(&self, path: &Path) -> usize {
+        if self.prefixes.is_empty() || path.leading_colon.is_none() {
+            return 0;
+        }
+        let segments: Vec<String> = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        for len in (2..=segments.len()).rev() {
+            if let Some(skip) = self.prefixes.get(&segments[..len]) {
+                return *skip;
+            }
+        }
+        0
+    }
+}
+
+// Occurrences of names within a module, not counting nested modules which
+// are a separate scope.
+struct Scope {
+    // Prelude items referred to by absolute path, and their name.
+    prelude: BTreeMap<Vec<String>, String>,
+    // Other absolute paths that could be imported, and how often they occur.
+    absolute: BTreeMap<Vec<String>, usize>,
+    // Names used as the first segment of a relative path.
+    relative: BTreeMap<String, usize>,
+    // Names appearing in macro input.
+    tokens: BTreeMap<String, usize>,
+    // Names declared, or used in any other way.
+    other: BTreeMap<String, usize>,
+    // Whether there is a glob import, which can shadow prelude names.
+    glob: bool,
+    no_std: bool,
+    edition: Option<Edition>,
+}
+
+impl Scope {
+    fn absolute(&mut self, path: &Path) {
+        let segments: Vec<String> = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+
+        for len in (2..=segments.len()).rev() {
+            if is_prelude(&segments[..len], self.no_std, self.edition) {
+                let name = segments[len - 1].clone();
+                self.prelude.insert(segments[..len].to_vec(), name);
+                return;
+            }
+        }
+
+        // Import the path up to the first segment that looks like a type or
+        // trait, or the whole path if all of it looks like modules. Generic
+        // arguments cannot be imported.
+        let mut len = 0;
+        for segment in &path.segments {
+            len += 1;
+            let ident = segment.ident.to_string();
+            if ident.starts_with(|ch: char| ch.is_ascii_uppercase()) {
+                break;
+            }
+            if !matches!(segment.arguments, PathArguments::None) && len < segments.len() {
+                return;
+            }
+        }
+        if len >= 2 {
+            *self.absolute.entry(segments[..len].to_vec()).or_insert(0) += 1;
+        }
+    }
+
+    fn tokens(&mut self, tokens: TokenStream, other: bool) {
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) => self.tokens(group.stream(), other),
+                TokenTree::Ident(ident) => {
+                    let count = if other {
+                        &mut self.other
+                    } else {
+                        &mut self.tokens
+                    };
+                    *count.entry(ident.to_string()).or_insert(0) += 1;
+                }
+                TokenTree::Punct(_) | TokenTree::Literal(_) => {}
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Scope {
+    fn visit_ident(&mut self, ident: &'ast Ident) {
+        *self.other.entry(ident.to_string()).or_insert(0) += 1;
+    }
+
+    fn visit_path(&mut self, path: &'ast Path) {
+        if path.leading_colon.is_some() {
+            self.absolute(path);
+        } else if let Some(first) = path.segments.first() {
+            *self.relative.entry(first.ident.to_string()).or_insert(0) += 1;
+        }
+        for segment in &path.segments {
+            self.visit_path_arguments(&segment.arguments);
+        }
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        self.visit_path(&mac.path);
+        self.tokens(mac.tokens.clone(), false);
+    }
+
+    fn visit_meta_list(&mut self, meta: &'ast MetaList) {
+        self.visit_path(&meta.path);
+        self.tokens(meta.tokens.clone(), false);
+    }
+
+    fn visit_item(&mut self, item: &'ast Item) {
+        match item {
+            Item::Mod(item) => {
+                for attr in &item.attrs {
+                    self.visit_attribute(attr);
+                }
+                self.visit_visibility(&item.vis);
+                self.visit_ident(&item.ident);
+            }
+            // The glob import of the prelude itself, as in macro-expanded code.
+            Item::Use(item) if has_attr(&item.attrs, "prelude_import") => {}
+            Item::Verbatim(tokens) => self.tokens(tokens.clone(), true),
+            _ => visit::visit_item(self, item),
+        }
+    }
+
+    fn visit_foreign_item(&mut self, foreign_item: &'ast ForeignItem) {
+        match foreign_item {
+            ForeignItem::Verbatim(tokens) => self.tokens(tokens.clone(), true),
+            _ => visit::visit_foreign_item(self, foreign_item),
+        }
+    }
+
+    fn visit_trait_item(&mut self, trait_item: &'ast TraitItem) {
+        match trait_item {
+            TraitItem::Verbatim(tokens) => self.tokens(tokens.clone(), true),
+            _ => visit::visit_trait_item(self, trait_item),
+        }
+    }
+
+    fn visit_impl_item(&mut self, impl_item: &'ast ImplItem) {
+        match impl_item {
+            ImplItem::Verbatim(tokens) => self.tokens(tokens.clone(), true),
+            _ => visit::visit_impl_item(self, impl_item),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        match expr {
+            Expr::Verbatim(tokens) => self.tokens(tokens.clone(), true),
+            _ => visit::visit_expr(self, expr),
+        }
+    }
+
+    fn visit_pat(&mut self, pat: &'ast Pat) {
+        match pat {
+            Pat::Verbatim(tokens) => self.tokens(tokens.clone(), true),
+            _ => visit::visit_pat(self, pat),
+        }
+    }
+
+    fn visit_type(&mut self, ty: &'ast Type) {
+        match ty {
+            Type::Verbatim(tokens) => self.tokens(tokens.clone(), true),
+            _ => visit::visit_type(self, ty),
+        }
+    }
+
+    fn visit_use_tree(&mut self, tree: &'ast UseTree) {
+        if let UseTree::Glob(_) = tree {
+            self.glob = true;
+        }
+        visit::visit_use_tree(self, tree);
+    }
+}
+
+fn has_attr(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident(name))
+}
+
+fn is_prelude(segments: &[String], no_std: bool, edition: Option<Edition>) -> bool {
+    let (krate, path) = match segments.split_first() {
+        Some((krate, path)) => (krate.as_str(), path.join("::")),
+        None => return false,
+    };
+    let core = match path.as_str() {
+        "option::Option"
+        | "option::Option::Some"
+        | "option::Option::None"
+        | "result::Result"
+        | "result::Result::Ok"
+        | "result::Result::Err"
+        | "clone::Clone"
+        | "marker::Copy"
+        | "marker::Send"
+        | "marker::Sized"
+        | "marker::Sync"
+        | "marker::Unpin"
+        | "default::Default"
+        | "cmp::PartialEq"
+        | "cmp::Eq"
+        | "cmp::PartialOrd"
+        | "cmp::Ord"
+        | "iter::Iterator"
+        | "iter::IntoIterator"
+        | "iter::Extend"
+        | "iter::DoubleEndedIterator"
+        | "iter::ExactSizeIterator"
+        | "convert::From"
+        | "convert::Into"
+        | "convert::AsRef"
+        | "convert::AsMut"
+        | "ops::Drop"
+        | "ops::Fn"
+        | "ops::FnMut"
+        | "ops::FnOnce"
+        | "mem::drop" => true,
+        "convert::TryFrom" | "convert::TryInto" | "iter::FromIterator" => {
+            edition >= Some(Edition::E2021)
+        }
+        "boxed::Box" | "string::String" | "string::ToString" | "vec::Vec" | "borrow::ToOwned" => {
+            return !no_std && (krate == "alloc" || krate == "std");
+        }
+        _ => return false,
+    };
+    core && (krate == "core" || krate == "std")
+}
@@ -1,11 +1,14 @@
-use crate::algorithm::Printer;
+use crate::algorithm::{Printer, Text};
+use crate::hygiene;
+use crate::mac;
+use alloc::string::{String, ToString};
 use proc_macro2::{Delimiter, Ident, Literal, Spacing, TokenStream, TokenTree};
 
 impl Printer {
     pub fn single_token(&mut self, token: Token, group_contents: fn(&mut Self, TokenStream)) {
         match token {
             Token::Group(delimiter, stream) => self.token_group(delimiter, stream, group_contents),
-            Token::Ident(ident) => self.ident(&ident),
+            Token::Ident(ident) => self.token_ident(&ident),
             Token::Punct(ch, _spacing) => self.token_punct(ch),
             Token::Literal(literal) => self.token_literal(&literal),
         }
@@ -31,15 +34,45 @@ impl Printer {
     }
 
     pub fn ident(&mut self, ident: &Ident) {
-        self.word(ident.to_string());
+        match self.hygiene_rename(ident) {
+            Some(renamed) => self.word(renamed),
+            None => self.unhygienic_ident(ident),
+        }
+    }
+
+    // Field names are not subject to hygiene, and are printed under their
+    // original name even if a local binding of the same name is renamed.
+    pub fn unhygienic_ident(&mut self, ident: &Ident) {
+        let ident = Text::display(ident);
+        if mac::requires_raw(&ident, self.config.edition) {
+            self.word("r#");
+        }
+        self.word(ident);
+    }
+
+    // Identifiers inside of a token stream are printed as is, because a
+    // keyword there is most likely meant as the keyword.
+    pub fn token_ident(&mut self, ident: &Ident) {
+        match self.hygiene_rename(ident) {
+            Some(renamed) => self.word(renamed),
+            None => self.word_display(ident),
+        }
+    }
+
+    pub(crate) fn hygiene_rename(&self, ident: &Ident) -> Option<String> {
+        if self.renames.is_empty() {
+            return None;
+        }
+        let key = (ident.to_string(), hygiene::context(ident.span()));
+        self.renames.get(&key).cloned()
     }
 
     pub fn token_punct(&mut self, ch: char) {
-        self.word(ch.to_string());
+        self.word(ch);
     }
 
     pub fn token_literal(&mut self, literal: &Literal) {
-        self.word(literal.to_string());
+        self.word_display(literal);
     }
 
     pub fn delimiter_open(&mut self, delimiter: Delimiter) {
@@ -1,13 +1,16 @@
 use crate::algorithm::Printer;
+use crate::config::Edition;
 use crate::fixup::FixupContext;
 use crate::iter::IterDelimited;
 use crate::path::PathKind;
 use crate::INDENT;
+use alloc::format;
 use proc_macro2::TokenStream;
+use syn::punctuated::Punctuated;
 use syn::{
-    Abi, BareFnArg, BareVariadic, ReturnType, Type, TypeArray, TypeBareFn, TypeGroup,
-    TypeImplTrait, TypeInfer, TypeMacro, TypeNever, TypeParen, TypePath, TypePtr, TypeReference,
-    TypeSlice, TypeTraitObject, TypeTuple,
+    Abi, BareFnArg, BareVariadic, ReturnType, Token, Type, TypeArray, TypeBareFn, TypeGroup,
+    TypeImplTrait, TypeInfer, TypeMacro, TypeNever, TypeParamBound, TypeParen, TypePath, TypePtr,
+    TypeReference, TypeSlice, TypeTraitObject, TypeTuple,
 };
 
 impl Printer {
@@ -74,12 +77,7 @@ impl Printer {
 
     fn type_impl_trait(&mut self, ty: &TypeImplTrait) {
         self.word("impl ");
-        for type_param_bound in ty.bounds.iter().delimited() {
-            if !type_param_bound.is_first {
-                self.word(" + ");
-            }
-            self.type_param_bound(&type_param_bound);
-        }
+        self.type_bounds(&ty.bounds);
     }
 
     fn type_infer(&mut self, ty: &TypeInfer) {
@@ -136,13 +134,28 @@ impl Printer {
     }
 
     fn type_trait_object(&mut self, ty: &TypeTraitObject) {
-        self.word("dyn ");
-        for type_param_bound in ty.bounds.iter().delimited() {
-            if !type_param_bound.is_first {
-                self.word(" + ");
+        if ty.dyn_token.is_some() || self.config.edition != Some(Edition::E2015) {
+            self.word("dyn ");
+        }
+        self.type_bounds(&ty.bounds);
+    }
+
+    fn type_bounds(&mut self, bounds: &Punctuated<TypeParamBound, Token![+]>) {
+        self.bounds_box();
+        for type_param_bound in bounds.iter().delimited() {
+            if type_param_bound.is_first {
+                // Keep the first bound at the indentation of the type, with
+                // only the bounds after it indented.
+                self.ibox(-INDENT);
+                self.type_param_bound(&type_param_bound);
+                self.end();
+            } else {
+                self.space();
+                self.word("+ ");
+                self.type_param_bound(&type_param_bound);
             }
-            self.type_param_bound(&type_param_bound);
         }
+        self.end();
     }
 
     fn type_tuple(&mut self, ty: &TypeTuple) {
@@ -165,14 +178,13 @@ impl Printer {
 
     #[cfg(not(feature = "verbatim"))]
     fn type_verbatim(&mut self, ty: &TokenStream) {
-        unimplemented!("Type::Verbatim `{}`", ty);
+        self.unsupported(format!("Type::Verbatim `{}`", ty));
     }
 
     #[cfg(feature = "verbatim")]
     fn type_verbatim(&mut self, tokens: &TokenStream) {
         use syn::parse::{Parse, ParseStream, Result};
-        use syn::punctuated::Punctuated;
-        use syn::{token, FieldsNamed, Token, TypeParamBound};
+        use syn::{token, FieldsNamed};
 
         enum TypeVerbatim {
             Ellipsis,
@@ -245,7 +257,10 @@ impl Printer {
 
         let ty: TypeVerbatim = match syn::parse2(tokens.clone()) {
             Ok(ty) => ty,
-            Err(_) => unimplemented!("Type::Verbatim `{}`", tokens),
+            Err(_) => {
+                self.unsupported(format!("Type::Verbatim `{}`", tokens));
+                return;
+            }
         };
 
         match ty {
@@ -256,9 +271,9 @@ impl Printer {
                 self.cbox(INDENT);
                 self.word("struct {");
                 self.hardbreak_if_nonempty();
-                for field in &ty.fields.named {
-                    self.field(field);
-                    self.word(",");
+                for field in ty.fields.named.iter().delimited() {
+                    self.field(&field);
+                    self.vertical_comma(field.is_last);
                     self.hardbreak();
                 }
                 self.offset(-INDENT);
@@ -269,9 +284,9 @@ impl Printer {
                 self.cbox(INDENT);
                 self.word("union {");
                 self.hardbreak_if_nonempty();
-                for field in &ty.fields.named {
-                    self.field(field);
-                    self.word(",");
+                for field in ty.fields.named.iter().delimited() {
+                    self.field(&field);
+                    self.vertical_comma(field.is_last);
                     self.hardbreak();
                 }
                 self.offset(-INDENT);
@@ -280,12 +295,7 @@ impl Printer {
             }
             TypeVerbatim::DynStar(ty) => {
                 self.word("dyn* ");
-                for type_param_bound in ty.bounds.iter().delimited() {
-                    if !type_param_bound.is_first {
-                        self.word(" + ");
-                    }
-                    self.type_param_bound(&type_param_bound);
-                }
+                self.type_bounds(&ty.bounds);
             }
             TypeVerbatim::MutSelf(bare_fn_arg) => {
                 self.word("mut self");
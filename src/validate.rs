@@ -0,0 +1,519 @@
+use crate::algorithm::Printer;
+use crate::fixup::FixupContext;
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use core::mem;
+use syn::visit::{self, Visit};
+use syn::{
+    Arm, BinOp, Block, Expr, ExprCall, ExprForLoop, ExprIf, ExprIndex, ExprMatch, ExprMethodCall,
+    ExprPath, ExprStruct, ExprWhile, File, ForeignItem, ImplItem, Item, ItemForeignMod, ItemImpl,
+    ItemMod, ItemTrait, Pat, PatStruct, PatTupleStruct, Path, QSelf, Stmt, TraitItem, Type,
+    TypeParamBound, TypePath,
+};
+
+/// Check a syntax tree for nodes that cannot be printed, or that would not
+/// mean the same thing after printing and parsing back.
+///
+/// Syntax trees produced by parsing source code are always valid. Trees built
+/// by a procedural macro or code generator can contain combinations that no
+/// parser would produce, such as a `let` expression outside of a condition,
+/// or verbatim tokens that the printer does not know how to lay out. Printing
+/// such a tree with [`unparse`][crate::unparse] either panics or produces code
+/// that does not compile. This function reports every such node instead.
+pub fn validate(file: &File) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        path: Vec::new(),
+        let_allowed: false,
+        diagnostics: Vec::new(),
+    };
+    validator.visit_file(file);
+    validator.diagnostics
+}
+
+/// A problem found by [`validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Location of the offending node, as the chain of its ancestors starting
+    /// from the file root, e.g. `fn main > stmt[0] > if > condition > let`.
+    pub path: String,
+    pub kind: DiagnosticKind,
+    /// Human readable explanation of the problem.
+    pub reason: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// A `let` expression somewhere other than the condition of `if` or
+    /// `while`, or a match guard.
+    LetOutsideCondition,
+    /// A `Verbatim` node whose tokens the printer does not understand.
+    InvalidVerbatim,
+    /// A `macro_rules!` definition whose body is not a sequence of
+    /// `(matcher) => {expander};` rules.
+    InvalidMacroRules,
+    /// A path with no segments, or with a qualified self type that refers
+    /// past the last segment.
+    InvalidPath,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}: {}", self.path, self.reason)
+    }
+}
+
+struct Validator {
+    path: Vec<String>,
+    // Whether a `let` expression is allowed here, i.e. we are in an if or
+    // while condition or a match guard, possibly nested in `&&` operands.
+    let_allowed: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator {
+    fn report(&mut self, kind: DiagnosticKind, reason: String) {
+        self.diagnostics.push(Diagnostic {
+            path: self.path.join(" > "),
+            kind,
+            reason,
+        });
+    }
+
+    fn report_unsupported(&mut self, kind: DiagnosticKind, probe: Printer) {
+        for reason in probe.into_unsupported() {
+            self.report(kind, reason);
+        }
+    }
+
+    fn nested(&mut self, segment: String, f: impl FnOnce(&mut Self)) {
+        self.path.push(segment);
+        f(self);
+        self.path.pop();
+    }
+
+    fn context(&mut self, let_allowed: bool, f: impl FnOnce(&mut Self)) {
+        let let_allowed = mem::replace(&mut self.let_allowed, let_allowed);
+        f(self);
+        self.let_allowed = let_allowed;
+    }
+
+    fn items(&mut self, items: &[Item]) {
+        for (i, item) in items.iter().enumerate() {
+            self.nested(item_segment(item, i), |v| v.visit_item(item));
+        }
+    }
+
+    fn check_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Let(_) if !self.let_allowed => self.report(
+                DiagnosticKind::LetOutsideCondition,
+                "`let` expression is only allowed in the condition of `if` or `while` or in a match guard, optionally joined by `&&`".to_owned(),
+            ),
+            Expr::Verbatim(_) => {
+                let mut probe = Printer::probe();
+                probe.expr(expr, FixupContext::NONE);
+                self.report_unsupported(DiagnosticKind::InvalidVerbatim, probe);
+            }
+            _ => {}
+        }
+    }
+
+    fn qself(&mut self, qself: &Option<QSelf>, path: &Path) {
+        if let Some(qself) = qself {
+            if qself.position >= path.segments.len() {
+                self.report(
+                    DiagnosticKind::InvalidPath,
+                    format!(
+                        "qualified self type refers to segment {} of a path with {} segments",
+                        qself.position,
+                        path.segments.len(),
+                    ),
+                );
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Validator {
+    fn visit_file(&mut self, file: &'ast File) {
+        for attr in &file.attrs {
+            self.visit_attribute(attr);
+        }
+        self.items(&file.items);
+    }
+
+    fn visit_item(&mut self, item: &'ast Item) {
+        let kind = match item {
+            Item::Macro(mac) if mac.ident.is_some() && mac.mac.path.is_ident("macro_rules") => {
+                Some(DiagnosticKind::InvalidMacroRules)
+            }
+            Item::Verbatim(_) => Some(DiagnosticKind::InvalidVerbatim),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            let mut probe = Printer::probe();
+            probe.item(item);
+            self.report_unsupported(kind, probe);
+        }
+        self.context(false, |v| visit::visit_item(v, item));
+    }
+
+    fn visit_item_mod(&mut self, item: &'ast ItemMod) {
+        for attr in &item.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_visibility(&item.vis);
+        if let Some((_brace, items)) = &item.content {
+            self.items(items);
+        }
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast ItemImpl) {
+        for attr in &item.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_generics(&item.generics);
+        if let Some((_negative_polarity, path, _for)) = &item.trait_ {
+            self.visit_path(path);
+        }
+        self.visit_type(&item.self_ty);
+        for (i, impl_item) in item.items.iter().enumerate() {
+            let segment = impl_item_segment(impl_item, i);
+            self.nested(segment, |v| v.visit_impl_item(impl_item));
+        }
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast ItemTrait) {
+        for attr in &item.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_visibility(&item.vis);
+        self.visit_generics(&item.generics);
+        for bound in &item.supertraits {
+            self.visit_type_param_bound(bound);
+        }
+        for (i, trait_item) in item.items.iter().enumerate() {
+            let segment = trait_item_segment(trait_item, i);
+            self.nested(segment, |v| v.visit_trait_item(trait_item));
+        }
+    }
+
+    fn visit_item_foreign_mod(&mut self, item: &'ast ItemForeignMod) {
+        for attr in &item.attrs {
+            self.visit_attribute(attr);
+        }
+        for (i, foreign_item) in item.items.iter().enumerate() {
+            let segment = foreign_item_segment(foreign_item, i);
+            self.nested(segment, |v| v.visit_foreign_item(foreign_item));
+        }
+    }
+
+    fn visit_foreign_item(&mut self, foreign_item: &'ast ForeignItem) {
+        if let ForeignItem::Verbatim(_) = foreign_item {
+            let mut probe = Printer::probe();
+            probe.foreign_item(foreign_item);
+            self.report_unsupported(DiagnosticKind::InvalidVerbatim, probe);
+        }
+        visit::visit_foreign_item(self, foreign_item);
+    }
+
+    fn visit_trait_item(&mut self, trait_item: &'ast TraitItem) {
+        if let TraitItem::Verbatim(_) = trait_item {
+            let mut probe = Printer::probe();
+            probe.trait_item(trait_item);
+            self.report_unsupported(DiagnosticKind::InvalidVerbatim, probe);
+        }
+        visit::visit_trait_item(self, trait_item);
+    }
+
+    fn visit_impl_item(&mut self, impl_item: &'ast ImplItem) {
+        if let ImplItem::Verbatim(_) = impl_item {
+            let mut probe = Printer::probe();
+            probe.impl_item(impl_item);
+            self.report_unsupported(DiagnosticKind::InvalidVerbatim, probe);
+        }
+        visit::visit_impl_item(self, impl_item);
+    }
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        self.context(false, |v| {
+            for (i, stmt) in block.stmts.iter().enumerate() {
+                v.nested(format!("stmt[{}]", i), |v| v.visit_stmt(stmt));
+            }
+        });
+    }
+
+    fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+        match stmt {
+            Stmt::Item(item) => self.nested(item_segment(item, 0), |v| v.visit_item(item)),
+            _ => visit::visit_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        // A `let` may be nested in the operands of `&&` within a condition.
+        let let_allowed = match expr {
+            Expr::Binary(e) if matches!(e.op, BinOp::And(_)) => self.let_allowed,
+            Expr::Group(_) => self.let_allowed,
+            _ => false,
+        };
+
+        self.nested(expr_segment(expr), |v| {
+            v.check_expr(expr);
+            v.context(let_allowed, |v| visit::visit_expr(v, expr));
+        });
+    }
+
+    fn visit_expr_if(&mut self, expr: &'ast ExprIf) {
+        for attr in &expr.attrs {
+            self.visit_attribute(attr);
+        }
+        self.nested("condition".to_owned(), |v| {
+            v.context(true, |v| v.visit_expr(&expr.cond));
+        });
+        self.visit_block(&expr.then_branch);
+        if let Some((_else, else_branch)) = &expr.else_branch {
+            self.context(false, |v| v.visit_expr(else_branch));
+        }
+    }
+
+    fn visit_expr_while(&mut self, expr: &'ast ExprWhile) {
+        for attr in &expr.attrs {
+            self.visit_attribute(attr);
+        }
+        self.nested("condition".to_owned(), |v| {
+            v.context(true, |v| v.visit_expr(&expr.cond));
+        });
+        self.visit_block(&expr.body);
+    }
+
+    fn visit_expr_for_loop(&mut self, expr: &'ast ExprForLoop) {
+        for attr in &expr.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_pat(&expr.pat);
+        self.context(false, |v| v.visit_expr(&expr.expr));
+        self.visit_block(&expr.body);
+    }
+
+    fn visit_expr_match(&mut self, expr: &'ast ExprMatch) {
+        for attr in &expr.attrs {
+            self.visit_attribute(attr);
+        }
+        self.context(false, |v| v.visit_expr(&expr.expr));
+        for (i, arm) in expr.arms.iter().enumerate() {
+            self.nested(format!("arm[{}]", i), |v| v.visit_arm(arm));
+        }
+    }
+
+    fn visit_arm(&mut self, arm: &'ast Arm) {
+        for attr in &arm.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_pat(&arm.pat);
+        if let Some((_if, guard)) = &arm.guard {
+            self.nested("guard".to_owned(), |v| {
+                v.context(true, |v| v.visit_expr(guard));
+            });
+        }
+        self.context(false, |v| v.visit_expr(&arm.body));
+    }
+
+    fn visit_expr_call(&mut self, expr: &'ast ExprCall) {
+        for attr in &expr.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_expr(&expr.func);
+        self.context(false, |v| {
+            for arg in &expr.args {
+                v.visit_expr(arg);
+            }
+        });
+    }
+
+    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
+        for attr in &expr.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_expr(&expr.receiver);
+        self.context(false, |v| {
+            if let Some(turbofish) = &expr.turbofish {
+                v.visit_angle_bracketed_generic_arguments(turbofish);
+            }
+            for arg in &expr.args {
+                v.visit_expr(arg);
+            }
+        });
+    }
+
+    fn visit_expr_index(&mut self, expr: &'ast ExprIndex) {
+        for attr in &expr.attrs {
+            self.visit_attribute(attr);
+        }
+        self.visit_expr(&expr.expr);
+        self.context(false, |v| v.visit_expr(&expr.index));
+    }
+
+    fn visit_expr_path(&mut self, expr: &'ast ExprPath) {
+        self.qself(&expr.qself, &expr.path);
+        visit::visit_expr_path(self, expr);
+    }
+
+    fn visit_expr_struct(&mut self, expr: &'ast ExprStruct) {
+        self.qself(&expr.qself, &expr.path);
+        visit::visit_expr_struct(self, expr);
+    }
+
+    fn visit_pat(&mut self, pat: &'ast Pat) {
+        if let Pat::Verbatim(_) = pat {
+            let mut probe = Printer::probe();
+            probe.pat(pat);
+            self.report_unsupported(DiagnosticKind::InvalidVerbatim, probe);
+        }
+        self.context(false, |v| visit::visit_pat(v, pat));
+    }
+
+    fn visit_pat_struct(&mut self, pat: &'ast PatStruct) {
+        self.qself(&pat.qself, &pat.path);
+        visit::visit_pat_struct(self, pat);
+    }
+
+    fn visit_pat_tuple_struct(&mut self, pat: &'ast PatTupleStruct) {
+        self.qself(&pat.qself, &pat.path);
+        visit::visit_pat_tuple_struct(self, pat);
+    }
+
+    fn visit_path(&mut self, path: &'ast Path) {
+        if path.segments.is_empty() {
+            self.report(
+                DiagnosticKind::InvalidPath,
+                "path has no segments".to_owned(),
+            );
+        }
+        visit::visit_path(self, path);
+    }
+
+    fn visit_type(&mut self, ty: &'ast Type) {
+        if let Type::Verbatim(_) = ty {
+            let mut probe = Printer::probe();
+            probe.ty(ty);
+            self.report_unsupported(DiagnosticKind::InvalidVerbatim, probe);
+        }
+        self.context(false, |v| visit::visit_type(v, ty));
+    }
+
+    fn visit_type_path(&mut self, ty: &'ast TypePath) {
+        self.qself(&ty.qself, &ty.path);
+        visit::visit_type_path(self, ty);
+    }
+
+    fn visit_type_param_bound(&mut self, bound: &'ast TypeParamBound) {
+        if let TypeParamBound::Verbatim(_) = bound {
+            let mut probe = Printer::probe();
+            probe.type_param_bound(bound);
+            self.report_unsupported(DiagnosticKind::InvalidVerbatim, probe);
+        }
+        visit::visit_type_param_bound(self, bound);
+    }
+}
+
+pub(crate) fn item_segment(item: &Item, index: usize) -> String {
+    match item {
+        Item::Const(item) => format!("const {}", item.ident),
+        Item::Enum(item) => format!("enum {}", item.ident),
+        Item::ExternCrate(item) => format!("extern crate {}", item.ident),
+        Item::Fn(item) => format!("fn {}", item.sig.ident),
+        Item::ForeignMod(_) => format!("extern[{}]", index),
+        Item::Impl(_) => format!("impl[{}]", index),
+        Item::Macro(item) => match &item.ident {
+            Some(ident) => format!("macro_rules! {}", ident),
+            None => format!("macro[{}]", index),
+        },
+        Item::Mod(item) => format!("mod {}", item.ident),
+        Item::Static(item) => format!("static {}", item.ident),
+        Item::Struct(item) => format!("struct {}", item.ident),
+        Item::Trait(item) => format!("trait {}", item.ident),
+        Item::TraitAlias(item) => format!("trait {}", item.ident),
+        Item::Type(item) => format!("type {}", item.ident),
+        Item::Union(item) => format!("union {}", item.ident),
+        Item::Use(_) => format!("use[{}]", index),
+        _ => format!("item[{}]", index),
+    }
+}
+
+fn foreign_item_segment(foreign_item: &ForeignItem, index: usize) -> String {
+    match foreign_item {
+        ForeignItem::Fn(item) => format!("fn {}", item.sig.ident),
+        ForeignItem::Static(item) => format!("static {}", item.ident),
+        ForeignItem::Type(item) => format!("type {}", item.ident),
+        _ => format!("item[{}]", index),
+    }
+}
+
+pub(crate) fn trait_item_segment(trait_item: &TraitItem, index: usize) -> String {
+    match trait_item {
+        TraitItem::Const(item) => format!("const {}", item.ident),
+        TraitItem::Fn(item) => format!("fn {}", item.sig.ident),
+        TraitItem::Type(item) => format!("type {}", item.ident),
+        _ => format!("item[{}]", index),
+    }
+}
+
+pub(crate) fn impl_item_segment(impl_item: &ImplItem, index: usize) -> String {
+    match impl_item {
+        ImplItem::Const(item) => format!("const {}", item.ident),
+        ImplItem::Fn(item) => format!("fn {}", item.sig.ident),
+        ImplItem::Type(item) => format!("type {}", item.ident),
+        _ => format!("item[{}]", index),
+    }
+}
+
+fn expr_segment(expr: &Expr) -> String {
+    let kind = match expr {
+        Expr::Array(_) => "array",
+        Expr::Assign(_) => "assign",
+        Expr::Async(_) => "async",
+        Expr::Await(_) => "await",
+        Expr::Binary(_) => "binary",
+        Expr::Block(_) => "block",
+        Expr::Break(_) => "break",
+        Expr::Call(_) => "call",
+        Expr::Cast(_) => "cast",
+        Expr::Closure(_) => "closure",
+        Expr::Const(_) => "const",
+        Expr::Continue(_) => "continue",
+        Expr::Field(_) => "field",
+        Expr::ForLoop(_) => "for",
+        Expr::Group(_) => "group",
+        Expr::If(_) => "if",
+        Expr::Index(_) => "index",
+        Expr::Infer(_) => "infer",
+        Expr::Let(_) => "let",
+        Expr::Lit(_) => "lit",
+        Expr::Loop(_) => "loop",
+        Expr::Macro(_) => "macro",
+        Expr::Match(_) => "match",
+        Expr::MethodCall(expr) => return format!("method call .{}", expr.method),
+        Expr::Paren(_) => "paren",
+        Expr::Path(_) => "path",
+        Expr::Range(_) => "range",
+        Expr::RawAddr(_) => "raw address",
+        Expr::Reference(_) => "reference",
+        Expr::Repeat(_) => "repeat",
+        Expr::Return(_) => "return",
+        Expr::Struct(_) => "struct",
+        Expr::Try(_) => "try",
+        Expr::TryBlock(_) => "try block",
+        Expr::Tuple(_) => "tuple",
+        Expr::Unary(_) => "unary",
+        Expr::Unsafe(_) => "unsafe",
+        Expr::Verbatim(_) => "verbatim",
+        Expr::While(_) => "while",
+        Expr::Yield(_) => "yield",
+        _ => "expr",
+    };
+    kind.to_owned()
+}
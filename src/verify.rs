@@ -0,0 +1,537 @@
+use crate::algorithm::Printer;
+use crate::config::Config;
+use crate::validate;
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use core::mem;
+#[cfg(feature = "std")]
+use std::error::Error;
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    AngleBracketedGenericArguments, Arm, Block, Expr, ExprArray, ExprCall, ExprClosure,
+    ExprMethodCall, ExprStruct, ExprTuple, FieldsNamed, FieldsUnnamed, File, Generics, ImplItem,
+    Item, ItemEnum, ItemTrait, LifetimeParam, MacroDelimiter, ParenthesizedGenericArguments,
+    PatSlice, PatStruct, PatTuple, PatTupleStruct, PredicateLifetime, PredicateType, Signature,
+    Stmt, StmtMacro, TraitItem, TraitItemType, TypeBareFn, TypeImplTrait, TypeParam,
+    TypeTraitObject, TypeTuple, UseGroup,
+};
+
+/// Pretty-print a syntax tree, then parse the result back and check that it
+/// is the same syntax tree.
+///
+/// The comparison ignores differences that do not affect the meaning of the
+/// code, such as parentheses, and the forms that the printer always
+/// normalizes, such as empty generic parameter lists.
+///
+/// This is substantially slower than [`unparse`][crate::unparse] and is
+/// intended for testing code generators.
+pub fn unparse_checked(file: &File) -> Result<String, RoundTripError> {
+    if let Some(diagnostic) = validate::validate(file).into_iter().next() {
+        return Err(RoundTripError {
+            path: diagnostic.path,
+            message: diagnostic.reason,
+        });
+    }
+
+    let output = match crate::try_unparse_with_config(file, &Config::default()) {
+        Ok(output) => output,
+        Err(err) => {
+            return Err(RoundTripError {
+                path: String::new(),
+                message: err.to_string(),
+            });
+        }
+    };
+    let mut reparsed = match syn::parse_file(&output) {
+        Ok(reparsed) => reparsed,
+        Err(err) => {
+            return Err(RoundTripError {
+                path: String::new(),
+                message: format!("printed code failed to parse: {}", err),
+            });
+        }
+    };
+
+    let mut original = file.clone();
+    normalize(&mut original);
+    normalize(&mut reparsed);
+    if original == reparsed {
+        Ok(output)
+    } else {
+        let mut path = Vec::new();
+        let message = items_difference(&mut path, &original.items, &reparsed.items)
+            .unwrap_or_else(|| "printed code has different file attributes".to_owned());
+        Err(RoundTripError {
+            path: path.join(" > "),
+            message,
+        })
+    }
+}
+
+/// Error returned by [`unparse_checked`].
+#[derive(Debug)]
+pub struct RoundTripError {
+    path: String,
+    message: String,
+}
+
+impl RoundTripError {
+    /// Location of the first node that did not survive printing and parsing,
+    /// as the chain of its ancestors starting from the file root, e.g.
+    /// `mod m > fn f > stmt[2]`. Empty if the problem is not with a
+    /// particular node.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Display for RoundTripError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            formatter.write_str(&self.message)
+        } else {
+            write!(formatter, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for RoundTripError {}
+
+/// Check that printing is a fixed point for this syntax tree: parsing the
+/// printed code and printing it again produces the same text.
+pub fn check_idempotent(file: &File) -> Result<(), IdempotencyError> {
+    check_idempotent_with_config(file, &Config::default())
+}
+
+/// Like [`check_idempotent`], printing both passes with the given
+/// configuration.
+pub fn check_idempotent_with_config(file: &File, config: &Config) -> Result<(), IdempotencyError> {
+    let first = crate::unparse_with_config(file, config);
+    let reparsed = match syn::parse_file(&first) {
+        Ok(reparsed) => reparsed,
+        Err(err) => {
+            return Err(IdempotencyError {
+                line: 0,
+                message: format!("printed code failed to parse: {}", err),
+            });
+        }
+    };
+    let second = crate::unparse_with_config(&reparsed, config);
+
+    let mut first_lines = first.lines();
+    let mut second_lines = second.lines();
+    let mut line = 0;
+    loop {
+        line += 1;
+        match (first_lines.next(), second_lines.next()) {
+            (None, None) => return Ok(()),
+            (Some(a), Some(b)) if a == b => {}
+            (a, b) => {
+                return Err(IdempotencyError {
+                    line,
+                    message: format!(
+                        "second pass differs from first\nfirst:  {}\nsecond: {}",
+                        a.unwrap_or("<end of file>"),
+                        b.unwrap_or("<end of file>"),
+                    ),
+                });
+            }
+        }
+    }
+}
+
+/// Error returned by [`check_idempotent`].
+#[derive(Debug)]
+pub struct IdempotencyError {
+    line: usize,
+    message: String,
+}
+
+impl IdempotencyError {
+    /// 1-based line number of the first line that differs between the first
+    /// and second pass, or 0 if the first pass did not parse.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl Display for IdempotencyError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            formatter.write_str(&self.message)
+        } else {
+            write!(formatter, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for IdempotencyError {}
+
+fn normalize(file: &mut File) {
+    FlattenParens.visit_file_mut(file);
+    AsIfPrinted.visit_file_mut(file);
+}
+
+// Parenthesization is the printer's responsibility, so only the structure
+// that the parentheses were there to preserve is compared.
+struct FlattenParens;
+
+impl VisitMut for FlattenParens {
+    fn visit_expr_mut(&mut self, e: &mut Expr) {
+        loop {
+            match e {
+                Expr::Paren(paren) => *e = mem::replace(&mut *paren.expr, Expr::PLACEHOLDER),
+                Expr::Group(group) => *e = mem::replace(&mut *group.expr, Expr::PLACEHOLDER),
+                _ => break,
+            }
+        }
+        visit_mut::visit_expr_mut(self, e);
+    }
+}
+
+// Syntax trees that print the same as their normalized form.
+struct AsIfPrinted;
+
+impl VisitMut for AsIfPrinted {
+    fn visit_angle_bracketed_generic_arguments_mut(
+        &mut self,
+        arguments: &mut AngleBracketedGenericArguments,
+    ) {
+        strip_trailing_punct(&mut arguments.args);
+        visit_mut::visit_angle_bracketed_generic_arguments_mut(self, arguments);
+    }
+
+    fn visit_arm_mut(&mut self, arm: &mut Arm) {
+        arm.comma = None;
+        visit_mut::visit_arm_mut(self, arm);
+    }
+
+    fn visit_expr_array_mut(&mut self, expr: &mut ExprArray) {
+        strip_trailing_punct(&mut expr.elems);
+        visit_mut::visit_expr_array_mut(self, expr);
+    }
+
+    fn visit_expr_call_mut(&mut self, expr: &mut ExprCall) {
+        strip_trailing_punct(&mut expr.args);
+        visit_mut::visit_expr_call_mut(self, expr);
+    }
+
+    fn visit_expr_closure_mut(&mut self, expr: &mut ExprClosure) {
+        strip_trailing_punct(&mut expr.inputs);
+        visit_mut::visit_expr_closure_mut(self, expr);
+    }
+
+    fn visit_expr_method_call_mut(&mut self, expr: &mut ExprMethodCall) {
+        strip_trailing_punct(&mut expr.args);
+        visit_mut::visit_expr_method_call_mut(self, expr);
+    }
+
+    fn visit_expr_struct_mut(&mut self, expr: &mut ExprStruct) {
+        strip_trailing_punct(&mut expr.fields);
+        visit_mut::visit_expr_struct_mut(self, expr);
+    }
+
+    fn visit_expr_tuple_mut(&mut self, expr: &mut ExprTuple) {
+        if expr.elems.len() > 1 {
+            strip_trailing_punct(&mut expr.elems);
+        }
+        visit_mut::visit_expr_tuple_mut(self, expr);
+    }
+
+    fn visit_fields_named_mut(&mut self, fields: &mut FieldsNamed) {
+        strip_trailing_punct(&mut fields.named);
+        visit_mut::visit_fields_named_mut(self, fields);
+    }
+
+    fn visit_fields_unnamed_mut(&mut self, fields: &mut FieldsUnnamed) {
+        strip_trailing_punct(&mut fields.unnamed);
+        visit_mut::visit_fields_unnamed_mut(self, fields);
+    }
+
+    fn visit_generics_mut(&mut self, generics: &mut Generics) {
+        strip_trailing_punct(&mut generics.params);
+        if generics.params.is_empty() {
+            generics.lt_token = None;
+            generics.gt_token = None;
+        }
+        if let Some(where_clause) = &mut generics.where_clause {
+            strip_trailing_punct(&mut where_clause.predicates);
+            if where_clause.predicates.is_empty() {
+                generics.where_clause = None;
+            }
+        }
+        visit_mut::visit_generics_mut(self, generics);
+    }
+
+    fn visit_item_enum_mut(&mut self, item: &mut ItemEnum) {
+        strip_trailing_punct(&mut item.variants);
+        visit_mut::visit_item_enum_mut(self, item);
+    }
+
+    fn visit_item_trait_mut(&mut self, item: &mut ItemTrait) {
+        strip_trailing_punct(&mut item.supertraits);
+        visit_mut::visit_item_trait_mut(self, item);
+    }
+
+    fn visit_lifetime_param_mut(&mut self, param: &mut LifetimeParam) {
+        strip_trailing_punct(&mut param.bounds);
+        if param.bounds.is_empty() {
+            param.colon_token = None;
+        }
+        visit_mut::visit_lifetime_param_mut(self, param);
+    }
+
+    fn visit_parenthesized_generic_arguments_mut(
+        &mut self,
+        arguments: &mut ParenthesizedGenericArguments,
+    ) {
+        strip_trailing_punct(&mut arguments.inputs);
+        visit_mut::visit_parenthesized_generic_arguments_mut(self, arguments);
+    }
+
+    fn visit_pat_slice_mut(&mut self, pat: &mut PatSlice) {
+        strip_trailing_punct(&mut pat.elems);
+        visit_mut::visit_pat_slice_mut(self, pat);
+    }
+
+    fn visit_pat_struct_mut(&mut self, pat: &mut PatStruct) {
+        strip_trailing_punct(&mut pat.fields);
+        visit_mut::visit_pat_struct_mut(self, pat);
+    }
+
+    fn visit_pat_tuple_mut(&mut self, pat: &mut PatTuple) {
+        if pat.elems.len() > 1 {
+            strip_trailing_punct(&mut pat.elems);
+        }
+        visit_mut::visit_pat_tuple_mut(self, pat);
+    }
+
+    fn visit_pat_tuple_struct_mut(&mut self, pat: &mut PatTupleStruct) {
+        strip_trailing_punct(&mut pat.elems);
+        visit_mut::visit_pat_tuple_struct_mut(self, pat);
+    }
+
+    fn visit_predicate_lifetime_mut(&mut self, predicate: &mut PredicateLifetime) {
+        strip_trailing_punct(&mut predicate.bounds);
+        visit_mut::visit_predicate_lifetime_mut(self, predicate);
+    }
+
+    fn visit_predicate_type_mut(&mut self, predicate: &mut PredicateType) {
+        strip_trailing_punct(&mut predicate.bounds);
+        visit_mut::visit_predicate_type_mut(self, predicate);
+    }
+
+    fn visit_signature_mut(&mut self, signature: &mut Signature) {
+        if signature.variadic.is_none() {
+            strip_trailing_punct(&mut signature.inputs);
+        }
+        visit_mut::visit_signature_mut(self, signature);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        if let Stmt::Expr(expr, semi) = stmt {
+            if let Expr::Macro(e) = expr {
+                if match e.mac.delimiter {
+                    MacroDelimiter::Brace(_) => true,
+                    MacroDelimiter::Paren(_) | MacroDelimiter::Bracket(_) => semi.is_some(),
+                } {
+                    let expr = match mem::replace(expr, Expr::PLACEHOLDER) {
+                        Expr::Macro(expr) => expr,
+                        _ => unreachable!(),
+                    };
+                    *stmt = Stmt::Macro(StmtMacro {
+                        attrs: expr.attrs,
+                        mac: expr.mac,
+                        semi_token: *semi,
+                    });
+                }
+            }
+        }
+        visit_mut::visit_stmt_mut(self, stmt);
+    }
+
+    fn visit_trait_item_type_mut(&mut self, item: &mut TraitItemType) {
+        strip_trailing_punct(&mut item.bounds);
+        visit_mut::visit_trait_item_type_mut(self, item);
+    }
+
+    fn visit_type_bare_fn_mut(&mut self, ty: &mut TypeBareFn) {
+        if ty.variadic.is_none() {
+            strip_trailing_punct(&mut ty.inputs);
+        }
+        visit_mut::visit_type_bare_fn_mut(self, ty);
+    }
+
+    fn visit_type_impl_trait_mut(&mut self, ty: &mut TypeImplTrait) {
+        strip_trailing_punct(&mut ty.bounds);
+        visit_mut::visit_type_impl_trait_mut(self, ty);
+    }
+
+    fn visit_type_param_mut(&mut self, param: &mut TypeParam) {
+        strip_trailing_punct(&mut param.bounds);
+        if param.bounds.is_empty() {
+            param.colon_token = None;
+        }
+        visit_mut::visit_type_param_mut(self, param);
+    }
+
+    fn visit_type_trait_object_mut(&mut self, ty: &mut TypeTraitObject) {
+        strip_trailing_punct(&mut ty.bounds);
+        visit_mut::visit_type_trait_object_mut(self, ty);
+    }
+
+    fn visit_type_tuple_mut(&mut self, ty: &mut TypeTuple) {
+        if ty.elems.len() > 1 {
+            strip_trailing_punct(&mut ty.elems);
+        }
+        visit_mut::visit_type_tuple_mut(self, ty);
+    }
+
+    fn visit_use_group_mut(&mut self, group: &mut UseGroup) {
+        strip_trailing_punct(&mut group.items);
+        visit_mut::visit_use_group_mut(self, group);
+    }
+}
+
+fn strip_trailing_punct<T, P>(punctuated: &mut Punctuated<T, P>) {
+    if punctuated.trailing_punct() {
+        let last = punctuated.pop().unwrap().into_value();
+        punctuated.push_value(last);
+    }
+}
+
+fn items_difference(
+    path: &mut Vec<String>,
+    original: &[Item],
+    reparsed: &[Item],
+) -> Option<String> {
+    for (i, (original, reparsed)) in original.iter().zip(reparsed).enumerate() {
+        if original == reparsed {
+            continue;
+        }
+        path.push(validate::item_segment(original, i));
+        let nested = match (original, reparsed) {
+            (Item::Fn(original), Item::Fn(reparsed)) if original.sig == reparsed.sig => {
+                block_difference(path, &original.block, &reparsed.block)
+            }
+            (Item::Mod(original), Item::Mod(reparsed)) if original.ident == reparsed.ident => {
+                match (&original.content, &reparsed.content) {
+                    (Some((_, original)), Some((_, reparsed))) => {
+                        items_difference(path, original, reparsed)
+                    }
+                    _ => None,
+                }
+            }
+            (Item::Impl(original), Item::Impl(reparsed)) => {
+                impl_items_difference(path, &original.items, &reparsed.items)
+            }
+            (Item::Trait(original), Item::Trait(reparsed)) => {
+                trait_items_difference(path, &original.items, &reparsed.items)
+            }
+            _ => None,
+        };
+        return Some(nested.unwrap_or_else(|| {
+            mismatch(render(|p| p.item(original)), render(|p| p.item(reparsed)))
+        }));
+    }
+    count_difference(original.len(), reparsed.len(), "items")
+}
+
+fn impl_items_difference(
+    path: &mut Vec<String>,
+    original: &[ImplItem],
+    reparsed: &[ImplItem],
+) -> Option<String> {
+    for (i, (original, reparsed)) in original.iter().zip(reparsed).enumerate() {
+        if original == reparsed {
+            continue;
+        }
+        path.push(validate::impl_item_segment(original, i));
+        let nested = match (original, reparsed) {
+            (ImplItem::Fn(original), ImplItem::Fn(reparsed)) if original.sig == reparsed.sig => {
+                block_difference(path, &original.block, &reparsed.block)
+            }
+            _ => None,
+        };
+        return Some(nested.unwrap_or_else(|| {
+            mismatch(
+                render(|p| p.impl_item(original)),
+                render(|p| p.impl_item(reparsed)),
+            )
+        }));
+    }
+    count_difference(original.len(), reparsed.len(), "items")
+}
+
+fn trait_items_difference(
+    path: &mut Vec<String>,
+    original: &[TraitItem],
+    reparsed: &[TraitItem],
+) -> Option<String> {
+    for (i, (original, reparsed)) in original.iter().zip(reparsed).enumerate() {
+        if original == reparsed {
+            continue;
+        }
+        path.push(validate::trait_item_segment(original, i));
+        let nested = match (original, reparsed) {
+            (TraitItem::Fn(original), TraitItem::Fn(reparsed)) if original.sig == reparsed.sig => {
+                match (&original.default, &reparsed.default) {
+                    (Some(original), Some(reparsed)) => block_difference(path, original, reparsed),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        return Some(nested.unwrap_or_else(|| {
+            mismatch(
+                render(|p| p.trait_item(original)),
+                render(|p| p.trait_item(reparsed)),
+            )
+        }));
+    }
+    count_difference(original.len(), reparsed.len(), "items")
+}
+
+fn block_difference(path: &mut Vec<String>, original: &Block, reparsed: &Block) -> Option<String> {
+    for (i, (original, reparsed)) in original.stmts.iter().zip(&reparsed.stmts).enumerate() {
+        if original == reparsed {
+            continue;
+        }
+        path.push(format!("stmt[{}]", i));
+        return Some(mismatch(
+            render(|p| p.stmt(original, true)),
+            render(|p| p.stmt(reparsed, true)),
+        ));
+    }
+    count_difference(original.stmts.len(), reparsed.stmts.len(), "statements")
+}
+
+fn count_difference(original: usize, reparsed: usize, what: &str) -> Option<String> {
+    if original == reparsed {
+        None
+    } else {
+        Some(format!(
+            "printed code parses as {} {} instead of {}",
+            reparsed, what, original,
+        ))
+    }
+}
+
+fn render(print: impl FnOnce(&mut Printer)) -> String {
+    let mut p = Printer::new();
+    print(&mut p);
+    p.eof()
+}
+
+fn mismatch(original: String, reparsed: String) -> String {
+    format!(
+        "printed code parses as a different syntax tree\noriginal:\n{}reparsed:\n{}",
+        original, reparsed,
+    )
+}
@@ -0,0 +1,33 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+
+// Expands to the pretty-printed source of a function in which two distinct
+// local variables are both spelled `x`, one with call-site hygiene and one
+// with mixed-site hygiene.
+#[proc_macro]
+pub fn unparse_hygiene(_input: TokenStream) -> TokenStream {
+    let call_site = quote::format_ident!("x", span = Span::call_site());
+    let mixed_site = quote::format_ident!("x", span = Span::mixed_site());
+    let syntax_tree: syn::File = syn::parse_quote! {
+        fn f(#call_site: i32) -> i32 {
+            let #mixed_site = #call_site + 1;
+            let s = S { #mixed_site };
+            #mixed_site * #call_site
+        }
+    };
+    let config = prettyplease::Config {
+        hygiene: true,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &config);
+    quote!(#pretty).into()
+}
+
+// Expands to the Debug representation of a compiler span, whose format the
+// printer relies on to tell hygiene contexts apart.
+#[proc_macro]
+pub fn span_debug(_input: TokenStream) -> TokenStream {
+    let debug = format!("{:?}", Span::mixed_site());
+    quote!(#debug).into()
+}
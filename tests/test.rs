@@ -1,5 +1,5 @@
 use indoc::indoc;
-use proc_macro2::{Delimiter, Group, TokenStream};
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream};
 use quote::quote;
 
 #[track_caller]
@@ -49,3 +49,649 @@ fn test_parenthesize_match_guard() {
         "},
     );
 }
+
+#[test]
+fn test_raw_identifier() {
+    let mut syntax_tree: syn::File = syn::parse_quote! {
+        struct S {
+            kind: Self,
+            value: crate::Value,
+        }
+    };
+    if let syn::Item::Struct(item) = &mut syntax_tree.items[0] {
+        for (field, name) in item.fields.iter_mut().zip(["type", "union"]) {
+            field.ident = Some(Ident::new(name, Span::call_site()));
+        }
+    }
+    let pretty = prettyplease::unparse(&syntax_tree);
+    let expected = indoc! {"
+        struct S {
+            r#type: Self,
+            union: crate::Value,
+        }
+    "};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_dense_array() {
+    let table = 0..40u8;
+    let names = ["A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L"];
+    let names = names.iter().map(|name| Ident::new(name, Span::call_site()));
+    test(
+        quote! {
+            const TABLE: [u8; 40] = [#(#table,)*];
+            fn main() {
+                let names = vec![#(Name::#names,)*];
+                let calls = [f(0), f(1), f(2), f(3), f(4), f(5), f(6), f(7), f(8), f(9), f(10), f(11)];
+            }
+        },
+        indoc! {"
+            const TABLE: [u8; 40] = [
+                0u8, 1u8, 2u8, 3u8, 4u8, 5u8, 6u8, 7u8, 8u8, 9u8, 10u8, 11u8, 12u8, 13u8, 14u8, 15u8,
+                16u8, 17u8, 18u8, 19u8, 20u8, 21u8, 22u8, 23u8, 24u8, 25u8, 26u8, 27u8, 28u8, 29u8,
+                30u8, 31u8, 32u8, 33u8, 34u8, 35u8, 36u8, 37u8, 38u8, 39u8,
+            ];
+            fn main() {
+                let names = vec![
+                    Name::A, Name::B, Name::C, Name::D, Name::E, Name::F, Name::G, Name::H, Name::I,
+                    Name::J, Name::K, Name::L,
+                ];
+                let calls = [
+                    f(0),
+                    f(1),
+                    f(2),
+                    f(3),
+                    f(4),
+                    f(5),
+                    f(6),
+                    f(7),
+                    f(8),
+                    f(9),
+                    f(10),
+                    f(11),
+                ];
+            }
+        "},
+    );
+}
+
+#[test]
+fn test_margin_and_indent() {
+    let syntax_tree: syn::File = syn::parse_quote! {
+        fn main() {
+            let _ = function_with_long_name(first_argument, second_argument);
+        }
+    };
+    let config = prettyplease::Config {
+        margin: 40,
+        indent: 2,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &config);
+    let expected = indoc! {"
+        fn main() {
+          let _ = function_with_long_name(
+            first_argument,
+            second_argument,
+          );
+        }
+    "};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_hex_int_tables() {
+    let syntax_tree: syn::File = syn::parse_quote! {
+        static CRC: [u16; 12] = [0, 4129, 8258, 12387, 16516, 20645, 24774, 28903, 33032, 37161, 41290, 45419];
+        static BYTES: [u8; 8] = [0u8, 10u8, 0x7fu8, 255u8, 1u8, 2u8, 3u8, 4u8];
+        static SHORT: [u8; 4] = [0, 10, 0x7f, 255];
+        static MIXED: [u8; 8] = [0, 10, 0x7f, 255u8, 1, 2, 3, 4];
+        static NEGATIVE: [i32; 2] = [1, -1];
+        fn f() -> bool {
+            data.ends_with(&[0, 0])
+        }
+    };
+    let config = prettyplease::Config {
+        margin: 60,
+        hex_int_tables: true,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &config);
+    let expected = indoc! {"
+        static CRC: [u16; 12] = [
+            0x0000, 0x1021, 0x2042, 0x3063, 0x4084, 0x50a5, 0x60c6,
+            0x70e7, 0x8108, 0x9129, 0xa14a, 0xb16b,
+        ];
+        static BYTES: [u8; 8] = [
+            0x00u8, 0x0au8, 0x7fu8, 0xffu8, 0x01u8, 0x02u8, 0x03u8,
+            0x04u8,
+        ];
+        static SHORT: [u8; 4] = [0, 10, 0x7f, 255];
+        static MIXED: [u8; 8] = [0, 10, 0x7f, 255u8, 1, 2, 3, 4];
+        static NEGATIVE: [i32; 2] = [1, -1];
+        fn f() -> bool {
+            data.ends_with(&[0, 0])
+        }
+    "};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_chain_layout() {
+    let syntax_tree: syn::File = syn::parse_quote! {
+        fn main() {
+            let builder = Builder::new().name("worker").stack_size(4096);
+            let value = config.get().unwrap();
+            let sum = values.iter().copied().filter(is_even).sum();
+        }
+    };
+    let config = prettyplease::Config {
+        chain_width: Some(40),
+        chain_links: Some(3),
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &config);
+    let expected = indoc! {r#"
+        fn main() {
+            let builder = Builder::new()
+                .name("worker")
+                .stack_size(4096);
+            let value = config.get().unwrap();
+            let sum = values
+                .iter()
+                .copied()
+                .filter(is_even)
+                .sum();
+        }
+    "#};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_overflow_last_arg() {
+    let syntax_tree: syn::File = syn::parse_quote! {
+        fn main() {
+            let handle = thread::spawn(name, move || {
+                let x = compute(a, b);
+                println!("{}", x);
+            });
+            map.insert(key, Value { first: 1, second: 2, third: vec![1, 2, 3], fourth: "a long string" });
+            list.iter().for_each(|item| { process(item); log(item); });
+            let result = function_with_a_long_name(first_argument_long_name, second_argument_long, |x| {
+                x + 1
+            });
+            let value = deserializer.erased_next_element(&mut seed_value).map(|opt| opt.unsafe_map(Out::take));
+        }
+    };
+    let config = prettyplease::Config {
+        overflow_last_arg: true,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &config);
+    let expected = indoc! {r#"
+        fn main() {
+            let handle = thread::spawn(name, move || {
+                let x = compute(a, b);
+                println!("{}", x);
+            });
+            map.insert(key, Value {
+                first: 1,
+                second: 2,
+                third: vec![1, 2, 3],
+                fourth: "a long string",
+            });
+            list.iter().for_each(|item| {
+                process(item);
+                log(item);
+            });
+            let result = function_with_a_long_name(
+                first_argument_long_name,
+                second_argument_long,
+                |x| { x + 1 },
+            );
+            let value = deserializer
+                .erased_next_element(&mut seed_value)
+                .map(|opt| opt.unsafe_map(Out::take));
+        }
+    "#};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_width_heuristics() {
+    let syntax_tree: syn::File = syn::parse_quote! {
+        #[derive(Clone, Copy, Debug)]
+        enum Shape {
+            Circle { radius: f64 },
+            Rectangle { width: f64, height: f64 },
+        }
+        fn main() {
+            let point = Point { x: 1, y: 2 };
+            let sum = add(first_value, second_value);
+            let list = [first_value, second_value];
+            let max = if a > b { a } else { b };
+            let abs = if value >= 0 { value } else { -value };
+        }
+    };
+    let config = prettyplease::Config {
+        fn_call_width: Some(20),
+        struct_lit_width: Some(16),
+        struct_variant_width: Some(15),
+        array_width: Some(20),
+        attr_fn_like_width: Some(15),
+        single_line_if_else_max_width: Some(25),
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &config);
+    let expected = indoc! {"
+        #[derive(
+            Clone,
+            Copy,
+            Debug
+        )]
+        enum Shape {
+            Circle { radius: f64 },
+            Rectangle {
+                width: f64,
+                height: f64,
+            },
+        }
+        fn main() {
+            let point = Point {
+                x: 1,
+                y: 2,
+            };
+            let sum = add(
+                first_value,
+                second_value,
+            );
+            let list = [
+                first_value,
+                second_value,
+            ];
+            let max = if a > b { a } else { b };
+            let abs = if value >= 0 {
+                value
+            } else {
+                -value
+            };
+        }
+    "};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_trailing_comma() {
+    let syntax_tree: syn::File = syn::parse_quote! {
+        use std::{fmt, io};
+        struct Point<T, U> where T: Copy, U: Copy {
+            x: T,
+            y: U,
+        }
+        fn main() {
+            let point = Point { x: 1, y: 2 };
+            let sum = add(first_value, second_value);
+            let list = [first_element_of_the_list, second_element_of_the_list, third_element_of_the_list];
+            match point {
+                Point { x: 0, .. } => {}
+                _ => origin(),
+            }
+        }
+    };
+    let always = prettyplease::Config {
+        trailing_comma: prettyplease::TrailingComma::Always,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &always);
+    let expected = indoc! {"
+        use std::{fmt, io,};
+        struct Point<T, U,>
+        where
+            T: Copy,
+            U: Copy,
+        {
+            x: T,
+            y: U,
+        }
+        fn main() {
+            let point = Point { x: 1, y: 2, };
+            let sum = add(first_value, second_value,);
+            let list = [
+                first_element_of_the_list,
+                second_element_of_the_list,
+                third_element_of_the_list,
+            ];
+            match point {
+                Point { x: 0, .. } => {},
+                _ => origin(),
+            }
+        }
+    "};
+    assert_eq!(pretty, expected);
+
+    let never = prettyplease::Config {
+        trailing_comma: prettyplease::TrailingComma::Never,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &never);
+    let expected = indoc! {"
+        use std::{fmt, io};
+        struct Point<T, U>
+        where
+            T: Copy,
+            U: Copy
+        {
+            x: T,
+            y: U
+        }
+        fn main() {
+            let point = Point { x: 1, y: 2 };
+            let sum = add(first_value, second_value);
+            let list = [
+                first_element_of_the_list,
+                second_element_of_the_list,
+                third_element_of_the_list
+            ];
+            match point {
+                Point { x: 0, .. } => {}
+                _ => origin()
+            }
+        }
+    "};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_brace_style() {
+    let syntax_tree: syn::File = syn::parse_quote! {
+        impl<T> Point<T> where T: Copy {
+            fn get(&self) -> T {
+                if self.valid { self.x } else { self.y }
+            }
+        }
+        fn main() {
+            if a { b(); } else { c(); }
+            match x { _ => {} }
+            loop { step(); }
+        }
+    };
+    let allman = prettyplease::Config {
+        brace_style: prettyplease::BraceStyle::AlwaysNextLine,
+        control_brace_style: prettyplease::ControlBraceStyle::AlwaysNextLine,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &allman);
+    let expected = indoc! {"
+        impl<T> Point<T>
+        where
+            T: Copy,
+        {
+            fn get(&self) -> T
+            {
+                if self.valid { self.x } else { self.y }
+            }
+        }
+        fn main()
+        {
+            if a
+            {
+                b();
+            }
+            else
+            {
+                c();
+            }
+            match x
+            {
+                _ => {}
+            }
+            loop
+            {
+                step();
+            }
+        }
+    "};
+    assert_eq!(pretty, expected);
+
+    let config = prettyplease::Config {
+        brace_style: prettyplease::BraceStyle::PreferSameLine,
+        control_brace_style: prettyplease::ControlBraceStyle::ClosingNextLine,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &config);
+    let expected = indoc! {"
+        impl<T> Point<T>
+        where
+            T: Copy, {
+            fn get(&self) -> T {
+                if self.valid { self.x } else { self.y }
+            }
+        }
+        fn main() {
+            if a {
+                b();
+            }
+            else {
+                c();
+            }
+            match x {
+                _ => {}
+            }
+            loop {
+                step();
+            }
+        }
+    "};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_where_clause_layout() {
+    let syntax_tree: syn::File = syn::parse_quote! {
+        fn short<T>(t: T) where T: Copy {}
+        fn long<Ipsum, Dolor>(ipsum: Ipsum, dolor: Dolor) where Ipsum: Eq + Clone, Dolor: Eq + Copy {}
+        fn bounds<T>() where T: FirstLongTraitName + SecondLongTraitName + ThirdLongTraitName + Send + Sync + Unpin {}
+    };
+    let legacy = prettyplease::Config {
+        where_style: prettyplease::WhereStyle::Legacy,
+        vertical_bounds: true,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &legacy);
+    let expected = indoc! {"
+        fn short<T>(t: T)
+            where T: Copy
+        {}
+        fn long<Ipsum, Dolor>(ipsum: Ipsum, dolor: Dolor)
+            where Ipsum: Eq + Clone,
+                  Dolor: Eq + Copy
+        {}
+        fn bounds<T>()
+            where T: FirstLongTraitName
+                + SecondLongTraitName
+                + ThirdLongTraitName
+                + Send
+                + Sync
+                + Unpin
+        {}
+    "};
+    assert_eq!(pretty, expected);
+
+    let same_line = prettyplease::Config {
+        where_same_line: true,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &same_line);
+    let expected = indoc! {"
+        fn short<T>(t: T) where T: Copy {}
+        fn long<Ipsum, Dolor>(ipsum: Ipsum, dolor: Dolor)
+        where
+            Ipsum: Eq + Clone,
+            Dolor: Eq + Copy,
+        {}
+        fn bounds<T>()
+        where
+            T: FirstLongTraitName + SecondLongTraitName + ThirdLongTraitName + Send + Sync
+                + Unpin,
+        {}
+    "};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_long_types() {
+    let syntax_tree: syn::File = syn::parse_quote! {
+        type Handler = Box<dyn Fn(&Request) -> Result<Response, HandlerError> + Send + Sync + 'static>;
+        type Callback = fn(context: *mut CallbackContext, status: StatusCode) -> Box<dyn Future<Output = Result<(), Error>> + Send>;
+        fn entries() -> impl Iterator<Item = (String, Value)> + DoubleEndedIterator + ExactSizeIterator {}
+    };
+    let config = prettyplease::Config {
+        margin: 60,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &config);
+    let expected = indoc! {"
+        type Handler = Box<
+            dyn Fn(&Request) -> Result<Response, HandlerError> + Send
+                + Sync + 'static,
+        >;
+        type Callback = fn(
+            context: *mut CallbackContext,
+            status: StatusCode,
+        ) -> Box<dyn Future<Output = Result<(), Error>> + Send>;
+        fn entries() -> impl Iterator<Item = (String, Value)>
+            + DoubleEndedIterator + ExactSizeIterator {}
+    "};
+    assert_eq!(pretty, expected);
+
+    let config = prettyplease::Config {
+        margin: 60,
+        vertical_bounds: true,
+        ..prettyplease::Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &config);
+    let expected = indoc! {"
+        type Handler = Box<
+            dyn Fn(&Request) -> Result<Response, HandlerError>
+                + Send
+                + Sync
+                + 'static,
+        >;
+        type Callback = fn(
+            context: *mut CallbackContext,
+            status: StatusCode,
+        ) -> Box<dyn Future<Output = Result<(), Error>> + Send>;
+        fn entries() -> impl Iterator<Item = (String, Value)>
+            + DoubleEndedIterator
+            + ExactSizeIterator {}
+    "};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_let_chain() {
+    let syntax_tree: syn::File = syn::parse_quote! {
+        fn main() {
+            if let Some(x) = a && x > 1 {
+                c();
+            }
+            if let Some(first_value) = compute_first() && first_value.is_valid() && let Some(second_value) = lookup(first_value) {
+                c();
+            }
+            while let Some(item) = iterator.next_item() && item.len() > minimum_length && let Ok(parsed) = item.parse() {
+                c();
+            }
+            match x {
+                Some(v) if let Some(w) = lookup_the_value(v) && w.is_ready_to_process() && let Ok(z) = w.parse() => {}
+                _ => {}
+            }
+        }
+    };
+    let pretty = prettyplease::unparse(&syntax_tree);
+    let expected = indoc! {"
+        fn main() {
+            if let Some(x) = a && x > 1 {
+                c();
+            }
+            if let Some(first_value) = compute_first()
+                && first_value.is_valid()
+                && let Some(second_value) = lookup(first_value)
+            {
+                c();
+            }
+            while let Some(item) = iterator.next_item()
+                && item.len() > minimum_length
+                && let Ok(parsed) = item.parse()
+            {
+                c();
+            }
+            match x {
+                Some(v)
+                    if let Some(w) = lookup_the_value(v)
+                        && w.is_ready_to_process()
+                        && let Ok(z) = w.parse() => {}
+                _ => {}
+            }
+        }
+    "};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_formatter_reuse() {
+    let mut too_deep: syn::Expr = syn::parse_quote!(x);
+    for _ in 0..10 {
+        too_deep = syn::parse_quote!(-#too_deep);
+    }
+    let files: Vec<syn::File> = vec![
+        syn::parse_quote! {
+            fn main() {
+                let _ = function_with_long_name(first_argument, second_argument);
+            }
+        },
+        syn::parse_quote! {
+            fn f() {
+                #too_deep
+            }
+        },
+        syn::parse_quote! {
+            struct S;
+        },
+    ];
+    let config = prettyplease::Config {
+        margin: 40,
+        max_depth: Some(8),
+        ..prettyplease::Config::default()
+    };
+
+    let mut formatter = prettyplease::Formatter::new(config.clone());
+    let first = formatter.try_format(&files[0]).unwrap();
+    assert_eq!(first, prettyplease::unparse_with_config(&files[0], &config));
+    assert_eq!(
+        first,
+        "fn main() {\n    let _ = function_with_long_name(\n        first_argument,\n        second_argument,\n    );\n}\n",
+    );
+
+    // Giving up partway through a file must not leave anything behind for the
+    // next one.
+    let error = formatter.try_format(&files[1]).unwrap_err();
+    assert_eq!(error.max_depth(), 8);
+    assert_eq!(formatter.try_format(&files[0]).unwrap(), first);
+    let third = formatter.try_format(&files[2]).unwrap();
+    assert_eq!(third, "struct S;\n");
+
+    let mut output = String::new();
+    formatter.format_into(&files[0], &mut output);
+    assert_eq!(output, first);
+    assert!(formatter.try_format_into(&files[1], &mut output).is_err());
+    assert_eq!(output, first);
+    formatter.format_into(&files[2], &mut output);
+    assert_eq!(output, third);
+    formatter.format_into(&files[0], &mut output);
+    assert_eq!(output, first);
+
+    let batch = [&files[0], &files[2], &files[0]];
+    let outputs = prettyplease::unparse_many(batch, &config);
+    assert_eq!(outputs, [first.clone(), third, first]);
+}
@@ -0,0 +1,140 @@
+use prettyplease::Config;
+use proc_macro2::Span;
+use std::mem;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    parse_quote, token, Expr, ExprBinary, ExprCast, ExprGroup, ExprMethodCall, ExprParen, File,
+    Item, Stmt,
+};
+
+const LEN: usize = 50_000;
+
+// Prints `fn f() { $expr }`. The syntax tree is leaked because dropping a
+// deeply nested syn expression recurses once per level.
+fn unparse(expr: Expr, config: &Config) -> Result<String, prettyplease::DepthError> {
+    let mut file: File = parse_quote!(
+        fn f() {}
+    );
+    match &mut file.items[0] {
+        Item::Fn(item) => item.block.stmts.push(Stmt::Expr(expr, None)),
+        _ => unreachable!(),
+    }
+    let result = prettyplease::try_unparse_with_config(&file, config);
+    mem::forget(file);
+    result
+}
+
+struct FlattenGroups;
+
+impl VisitMut for FlattenGroups {
+    fn visit_expr_mut(&mut self, e: &mut Expr) {
+        while let Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) = e {
+            *e = mem::replace(&mut **expr, Expr::PLACEHOLDER);
+        }
+        visit_mut::visit_expr_mut(self, e);
+    }
+}
+
+fn nested_parens(n: usize) -> Expr {
+    let mut expr: Expr = parse_quote!(a);
+    for _ in 0..n {
+        let mut paren: ExprParen = parse_quote!((_));
+        *paren.expr = expr;
+        expr = Expr::Paren(paren);
+    }
+    expr
+}
+
+#[test]
+fn test_binary_chain() {
+    let mut expr: Expr = parse_quote!(a);
+    for _ in 0..LEN {
+        let mut binary: ExprBinary = parse_quote!(_ + b);
+        *binary.left = expr;
+        expr = Expr::Binary(binary);
+    }
+    let pretty = unparse(expr, &Config::default()).unwrap();
+    assert!(pretty.starts_with("fn f() {\n    a + b + b"));
+    assert_eq!(pretty.matches(" + b").count(), LEN);
+}
+
+#[test]
+fn test_method_chain() {
+    let mut expr: Expr = parse_quote!(a);
+    for _ in 0..LEN {
+        let mut call: ExprMethodCall = parse_quote!(_.f());
+        *call.receiver = expr;
+        expr = Expr::MethodCall(call);
+    }
+    let pretty = unparse(expr, &Config::default()).unwrap();
+    assert!(pretty.starts_with("fn f() {\n    a.f()\n        .f()\n"));
+    assert_eq!(pretty.matches(".f()").count(), LEN);
+}
+
+#[test]
+fn test_max_depth() {
+    let expr = nested_parens(300);
+
+    let pretty = unparse(expr.clone(), &Config::default()).unwrap();
+    assert!(pretty.contains(&format!("{}a{}", "(".repeat(300), ")".repeat(300))));
+
+    let config = Config {
+        max_depth: Some(256),
+        ..Config::default()
+    };
+    let error = unparse(expr, &config).unwrap_err();
+    assert_eq!(error.max_depth(), 256);
+    assert_eq!(
+        error.to_string(),
+        "expressions are nested more than 256 levels deep",
+    );
+}
+
+#[test]
+fn test_default_max_depth() {
+    let max_depth = Config::default().max_depth.unwrap();
+    let error = unparse(nested_parens(max_depth + 1), &Config::default()).unwrap_err();
+    assert_eq!(error.max_depth(), max_depth);
+
+    let config = Config {
+        max_depth: None,
+        ..Config::default()
+    };
+    unparse(nested_parens(max_depth + 1), &config).unwrap();
+}
+
+// `a = a.. as T` parses as a cast of the whole assignment, so the assignment
+// needs no parentheses there. Invisible groups, as produced by macro_rules, do
+// not change that, but past the depth to which the printer looks down the
+// right-hand side it parenthesizes instead. Either way the output must parse
+// back to the same expression.
+#[test]
+fn test_scan_depth() {
+    for (len, parenthesized) in [(10, false), (100, true)] {
+        let mut expr: Expr = parse_quote!(a = a..);
+        for _ in 0..len {
+            expr = Expr::Group(ExprGroup {
+                attrs: Vec::new(),
+                group_token: token::Group(Span::call_site()),
+                expr: Box::new(expr),
+            });
+        }
+        let mut cast: ExprCast = parse_quote!(_ as T);
+        *cast.expr = expr;
+
+        let mut file: File = parse_quote!(
+            fn f() {}
+        );
+        match &mut file.items[0] {
+            Item::Fn(item) => item.block.stmts.push(Stmt::Expr(Expr::Cast(cast), None)),
+            _ => unreachable!(),
+        }
+        let pretty = prettyplease::unparse(&file);
+        assert_eq!(pretty.starts_with("fn f() {\n    ("), parenthesized);
+
+        let mut reparsed = syn::parse_file(&pretty).unwrap();
+        FlattenGroups.visit_file_mut(&mut reparsed);
+        FlattenGroups.visit_file_mut(&mut file);
+        assert_eq!(reparsed, file);
+    }
+}
@@ -0,0 +1,204 @@
+use indoc::indoc;
+use prettyplease::{Config, Edition};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{File, Item};
+
+#[track_caller]
+#[allow(clippy::needless_update)]
+fn test(edition: Edition, syntax_tree: &File, expected: &str) {
+    let config = Config {
+        edition: Some(edition),
+        ..Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(syntax_tree, &config);
+    assert_eq!(pretty, expected);
+}
+
+fn parse(tokens: TokenStream) -> File {
+    syn::parse2(tokens).unwrap()
+}
+
+#[test]
+fn test_keyword_escape() {
+    let mut syntax_tree = parse(quote! {
+        fn f() {
+            m!(async);
+        }
+    });
+    if let Item::Fn(item) = &mut syntax_tree.items[0] {
+        item.sig.ident = Ident::new("async", Span::call_site());
+    }
+
+    test(
+        Edition::E2015,
+        &syntax_tree,
+        indoc! {"
+            fn async() {
+                m!(async);
+            }
+        "},
+    );
+    test(
+        Edition::E2018,
+        &syntax_tree,
+        indoc! {"
+            fn r#async() {
+                m!(async);
+            }
+        "},
+    );
+
+    if let Item::Fn(item) = &mut syntax_tree.items[0] {
+        item.sig.ident = Ident::new("gen", Span::call_site());
+    }
+    test(
+        Edition::E2021,
+        &syntax_tree,
+        "fn gen() {\n    m!(async);\n}\n",
+    );
+    test(
+        Edition::E2024,
+        &syntax_tree,
+        "fn r#gen() {\n    m!(async);\n}\n",
+    );
+}
+
+#[test]
+fn test_bare_trait_object() {
+    let syntax_tree = parse(quote! {
+        type T = Box<Trait + 'static>;
+        type U = Box<dyn Trait>;
+    });
+    test(
+        Edition::E2015,
+        &syntax_tree,
+        indoc! {"
+            type T = Box<Trait + 'static>;
+            type U = Box<dyn Trait>;
+        "},
+    );
+    test(
+        Edition::E2021,
+        &syntax_tree,
+        indoc! {"
+            type T = Box<dyn Trait + 'static>;
+            type U = Box<dyn Trait>;
+        "},
+    );
+}
+
+#[test]
+fn test_edition_2024() {
+    let syntax_tree = parse(quote! {
+        extern "C" {
+            fn f();
+        }
+        #[no_mangle]
+        pub extern "C" fn g() {}
+    });
+    test(
+        Edition::E2024,
+        &syntax_tree,
+        indoc! {r#"
+            unsafe extern "C" {
+                fn f();
+            }
+            #[unsafe(no_mangle)]
+            pub extern "C" fn g() {}
+        "#},
+    );
+}
+
+#[test]
+fn test_fragment_specifier() {
+    let syntax_tree = parse(quote! {
+        macro_rules! m {
+            ($e:expr_2021, $p:pat_param) => {};
+        }
+    });
+    test(
+        Edition::E2018,
+        &syntax_tree,
+        indoc! {"
+            macro_rules! m {
+                ($e:expr, $p:pat) => {};
+            }
+        "},
+    );
+    test(
+        Edition::E2021,
+        &syntax_tree,
+        indoc! {"
+            macro_rules! m {
+                ($e:expr, $p:pat_param) => {};
+            }
+        "},
+    );
+    test(
+        Edition::E2024,
+        &syntax_tree,
+        indoc! {"
+            macro_rules! m {
+                ($e:expr_2021, $p:pat_param) => {};
+            }
+        "},
+    );
+}
+
+#[test]
+fn test_unsafe_attribute() {
+    let syntax_tree = parse(quote! {
+        #[unsafe(no_mangle)]
+        pub extern "C" fn f() {}
+    });
+    for edition in [
+        Edition::E2015,
+        Edition::E2018,
+        Edition::E2021,
+        Edition::E2024,
+    ] {
+        test(
+            edition,
+            &syntax_tree,
+            indoc! {r#"
+                #[unsafe(no_mangle)]
+                pub extern "C" fn f() {}
+            "#},
+        );
+    }
+}
+
+#[test]
+fn test_no_edition() {
+    let syntax_tree = parse(quote! {
+        macro_rules! m {
+            ($e:expr_2021, $p:pat_param) => {};
+        }
+        extern "C" {
+            fn f();
+        }
+        #[no_mangle]
+        pub extern "C" fn g() {
+            try!(m!(try));
+        }
+        type T = Box<Trait + 'static>;
+    });
+    let pretty = prettyplease::unparse_with_config(&syntax_tree, &Config::default());
+    assert_eq!(
+        pretty,
+        indoc! {r#"
+            macro_rules! m {
+                ($e:expr_2021, $p:pat_param) => {};
+            }
+            extern "C" {
+                fn f();
+            }
+            #[no_mangle]
+            pub extern "C" fn g() {
+                try!(m!(try));
+            }
+            type T = Box<dyn Trait + 'static>;
+        "#},
+    );
+}
@@ -0,0 +1,31 @@
+use indoc::indoc;
+
+#[test]
+fn test_hygiene() {
+    let pretty = prettyplease_hygiene::unparse_hygiene!();
+    let expected = indoc! {"
+        fn f(x: i32) -> i32 {
+            let x_1 = x + 1;
+            let s = S { x: x_1 };
+            x_1 * x
+        }
+    "};
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_span_debug_format() {
+    // Hygiene contexts are read from the `#N` prefix of a span's Debug
+    // representation. If this fails, the compiler changed the format and
+    // hygiene::context needs to be updated.
+    let debug = prettyplease_hygiene::span_debug!();
+    let context = debug
+        .strip_prefix('#')
+        .and_then(|rest| rest.split(' ').next())
+        .and_then(|context| context.parse::<u32>().ok());
+    assert!(
+        context.is_some(),
+        "unrecognized Debug format of compiler span: {:?}",
+        debug,
+    );
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "verify")]
+
+use prettyplease::{Config, Edition};
+use std::fs;
+use std::path::Path;
+
+fn check(path: &Path) {
+    let content = fs::read_to_string(path).unwrap();
+    let syntax_tree = syn::parse_file(&content).unwrap();
+    if let Err(err) = prettyplease::check_idempotent(&syntax_tree) {
+        panic!("{}: {}", path.display(), err);
+    }
+
+    let config = Config {
+        edition: Some(Edition::E2024),
+        ..Config::default()
+    };
+    if let Err(err) = prettyplease::check_idempotent_with_config(&syntax_tree, &config) {
+        panic!("{} (edition 2024): {}", path.display(), err);
+    }
+}
+
+#[test]
+fn test_cargo_expand() {
+    for entry in fs::read_dir("cargo-expand").unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "rs")
+        {
+            check(&path);
+        }
+    }
+}
+
+#[test]
+fn test_examples() {
+    check(Path::new("examples/input.rs"));
+}
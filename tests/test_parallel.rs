@@ -0,0 +1,56 @@
+#![cfg(feature = "rayon")]
+
+use prettyplease::algorithm::Printer;
+use prettyplease::Config;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn check(path: &Path, config: &Config) {
+    // Items are printed sequentially if there is only one thread.
+    env::set_var("RAYON_NUM_THREADS", "4");
+
+    let content = fs::read_to_string(path).unwrap();
+    let syntax_tree = syn::parse_file(&content).unwrap();
+
+    let mut p = Printer::with_config(config.clone());
+    p.file(&syntax_tree);
+    let sequential = p.eof();
+
+    let parallel = prettyplease::unparse_with_config(&syntax_tree, config);
+    assert!(
+        parallel == sequential,
+        "{}: parallel output differs",
+        path.display(),
+    );
+}
+
+#[test]
+fn test_cargo_expand() {
+    let configs = [
+        Config::default(),
+        Config {
+            margin: 60,
+            indent: 2,
+            shorten_prelude_paths: true,
+            import_repeated_paths: true,
+            ..Config::default()
+        },
+    ];
+    for entry in fs::read_dir("cargo-expand").unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "rs")
+        {
+            for config in &configs {
+                check(&path, config);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_examples() {
+    check(Path::new("examples/input.rs"), &Config::default());
+}
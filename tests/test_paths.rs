@@ -0,0 +1,96 @@
+use indoc::indoc;
+use prettyplease::Config;
+use syn::{parse_quote, File};
+
+#[track_caller]
+fn test(syntax_tree: &File, expected: &str) {
+    let config = Config {
+        shorten_prelude_paths: true,
+        import_repeated_paths: true,
+        ..Config::default()
+    };
+    let pretty = prettyplease::unparse_with_config(syntax_tree, &config);
+    assert_eq!(pretty, expected);
+}
+
+#[test]
+fn test_prelude() {
+    let syntax_tree: File = parse_quote! {
+        fn f(x: ::core::option::Option<::std::string::String>) -> ::std::vec::Vec<u8> {
+            match x {
+                ::core::option::Option::Some(s) => s.into_bytes(),
+                ::core::option::Option::None => ::std::vec::Vec::new(),
+            }
+        }
+    };
+    test(
+        &syntax_tree,
+        indoc! {"
+            fn f(x: Option<String>) -> Vec<u8> {
+                match x {
+                    Some(s) => s.into_bytes(),
+                    None => Vec::new(),
+                }
+            }
+        "},
+    );
+}
+
+#[test]
+fn test_prelude_shadowed() {
+    let syntax_tree: File = parse_quote! {
+        struct Vec;
+        mod m {
+            fn f() -> ::std::vec::Vec<::std::string::String> {
+                ::std::vec::Vec::new()
+            }
+        }
+        fn g() -> ::std::vec::Vec<::std::string::String> {
+            ::std::vec::Vec::new()
+        }
+    };
+    test(
+        &syntax_tree,
+        indoc! {"
+            struct Vec;
+            mod m {
+                fn f() -> Vec<String> {
+                    Vec::new()
+                }
+            }
+            fn g() -> ::std::vec::Vec<String> {
+                ::std::vec::Vec::new()
+            }
+        "},
+    );
+}
+
+#[test]
+fn test_import_repeated() {
+    let syntax_tree: File = parse_quote! {
+        #![allow(dead_code)]
+        fn f(map: &::std::collections::HashMap<u8, u8>) -> ::std::collections::HashMap<u8, u8> {
+            let _ = ::std::fmt::Debug::fmt;
+            map.clone()
+        }
+        fn g(fmt: &mut ::std::fmt::Formatter) {
+            let _ = ::serde::de::Error::custom("...");
+            let _ = fmt;
+        }
+    };
+    test(
+        &syntax_tree,
+        indoc! {r#"
+            #![allow(dead_code)]
+            use ::std::collections::HashMap;
+            fn f(map: &HashMap<u8, u8>) -> HashMap<u8, u8> {
+                let _ = ::std::fmt::Debug::fmt;
+                map.clone()
+            }
+            fn g(fmt: &mut ::std::fmt::Formatter) {
+                let _ = ::serde::de::Error::custom("...");
+                let _ = fmt;
+            }
+        "#},
+    );
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "verify")]
+
+use syn::{parse_quote, File, Item, Stmt};
+
+#[test]
+fn test_round_trip() {
+    let file: File = parse_quote! {
+        fn main() {
+            let _ = (a + b) * c;
+            if (S { x }).ok() {}
+        }
+    };
+    let output = prettyplease::unparse_checked(&file).unwrap();
+    assert_eq!(output, prettyplease::unparse(&file));
+}
+
+#[test]
+fn test_divergent_stmt() {
+    let mut file: File = parse_quote! {
+        mod m {
+            fn f() {
+                a + b;
+                -c;
+            }
+        }
+    };
+    let item = match &mut file.items[0] {
+        Item::Mod(item) => item,
+        _ => unreachable!(),
+    };
+    let item = match &mut item.content.as_mut().unwrap().1[0] {
+        Item::Fn(item) => item,
+        _ => unreachable!(),
+    };
+    match &mut item.block.stmts[0] {
+        Stmt::Expr(_, semi) => *semi = None,
+        _ => unreachable!(),
+    }
+
+    let error = prettyplease::unparse_checked(&file).unwrap_err();
+    assert_eq!(error.path(), "mod m > fn f > stmt[0]");
+    assert_eq!(
+        error.to_string(),
+        "mod m > fn f > stmt[0]: printed code parses as a different syntax tree\n\
+         original:\n\
+         a + b\n\
+         reparsed:\n\
+         a + b - c;\n",
+    );
+}
+
+#[test]
+fn test_invalid() {
+    let file: File = parse_quote! {
+        fn main() {
+            f(let x = y);
+        }
+    };
+    let error = prettyplease::unparse_checked(&file).unwrap_err();
+    assert_eq!(error.path(), "fn main > stmt[0] > call > let");
+}
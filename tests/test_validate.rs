@@ -0,0 +1,99 @@
+use prettyplease::{Diagnostic, DiagnosticKind};
+use quote::quote;
+use syn::{parse_quote, Expr, File, Item, Stmt};
+
+fn diagnostics(file: &File) -> Vec<(String, DiagnosticKind)> {
+    prettyplease::validate(file)
+        .into_iter()
+        .map(|Diagnostic { path, kind, .. }| (path, kind))
+        .collect()
+}
+
+#[test]
+fn test_valid() {
+    let file: File = parse_quote! {
+        fn main() {
+            if let Some(x) = y && let Some(z) = x {}
+            while let Some(x) = stack.pop() {}
+            match x {
+                Some(x) if let Ok(y) = x => {}
+                _ => {}
+            }
+            if (S {}).ok() {}
+            let _ = S {};
+        }
+    };
+    assert_eq!(diagnostics(&file), []);
+}
+
+#[test]
+fn test_let_outside_condition() {
+    let file: File = parse_quote! {
+        fn main() {
+            f(let x = y);
+            if a || let Some(x) = y {}
+        }
+    };
+    assert_eq!(
+        diagnostics(&file),
+        [
+            (
+                "fn main > stmt[0] > call > let".to_owned(),
+                DiagnosticKind::LetOutsideCondition,
+            ),
+            (
+                "fn main > stmt[1] > if > condition > binary > let".to_owned(),
+                DiagnosticKind::LetOutsideCondition,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn test_struct_in_condition() {
+    let mut file: File = parse_quote! {
+        mod m {
+            fn f() {
+                if x {}
+            }
+        }
+    };
+    let item = match &mut file.items[0] {
+        Item::Mod(item) => item,
+        _ => unreachable!(),
+    };
+    let item = match &mut item.content.as_mut().unwrap().1[0] {
+        Item::Fn(item) => item,
+        _ => unreachable!(),
+    };
+    match &mut item.block.stmts[0] {
+        Stmt::Expr(Expr::If(expr), None) => *expr.cond = parse_quote!(S { x: 1 }.ok()),
+        _ => unreachable!(),
+    }
+    // The printer parenthesizes the struct literal itself.
+    assert_eq!(diagnostics(&file), []);
+    assert_eq!(
+        prettyplease::unparse(&file),
+        "mod m {\n    fn f() {\n        if (S { x: 1 }).ok() {}\n    }\n}\n",
+    );
+}
+
+#[test]
+fn test_invalid_verbatim_and_macro_rules() {
+    let mut file: File = parse_quote! {
+        macro_rules! m {
+            () => {};
+            garbage
+        }
+    };
+    file.items.push(Item::Verbatim(quote!(+ + +)));
+    let diagnostics = prettyplease::validate(&file);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidMacroRules);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "macro_rules! m: bad macro_rules syntax",
+    );
+    assert_eq!(diagnostics[1].kind, DiagnosticKind::InvalidVerbatim);
+    assert_eq!(diagnostics[1].path, "item[1]");
+}